@@ -0,0 +1,781 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+use uefi::{prelude::*, proto::media::block::BlockIO};
+
+use crate::{
+    config::Config,
+    error::{Result, ResultFixupExt},
+    greeter::Greeter,
+    hash_password, hex, info, mbr_sha256, menu,
+    opal::{
+        session::{self, locking_range_ace_uid, OpalSession, Properties},
+        uid, Authority, LockingState,
+    },
+    read_file, read_new_password,
+    secure_device::{Geometry, LockingFlags, SecureDevice},
+};
+
+/// What was done so far, shown above every question
+/// so that it's clear what state the drive is left in
+struct Transcript(String);
+
+enum Answer {
+    Yes,
+    Skip,
+    Abort,
+}
+
+impl Transcript {
+    fn new(title: String) -> Self {
+        log::info!("{}", title);
+        Self(title + "\n")
+    }
+
+    fn note(&mut self, line: &str) {
+        log::info!("{}", line);
+        self.0.push_str("  ");
+        self.0.push_str(line);
+        self.0.push('\n');
+    }
+
+    fn confirm(&self, st: &mut SystemTable<Boot>, question: &str) -> Result<Answer> {
+        let title = format!("{}\n{}", self.0, question);
+        Ok(
+            match menu::choose(st, &title, &["yes", "skip", "abort"], 2, None)? {
+                0 => Answer::Yes,
+                1 => Answer::Skip,
+                _ => Answer::Abort,
+            },
+        )
+    }
+
+    /// Asks for the step and does it, returns false if the rest should not be done
+    fn step(
+        &mut self,
+        st: &mut SystemTable<Boot>,
+        question: &str,
+        action: impl FnOnce(&mut SystemTable<Boot>, &mut Self) -> Result,
+    ) -> Result<bool> {
+        match self.confirm(st, question)? {
+            Answer::Yes => match action(st, self) {
+                Ok(()) => Ok(true),
+                Err(e) => {
+                    self.note(&format!("failed: {:?}, stopping here", e));
+                    Ok(false)
+                }
+            },
+            Answer::Skip => {
+                self.note(&format!("skipped: {}", question));
+                Ok(true)
+            }
+            Answer::Abort => {
+                self.note("aborted");
+                Ok(false)
+            }
+        }
+    }
+
+    fn show(&self, st: &mut SystemTable<Boot>) -> Result {
+        menu::choose(st, &self.0, &["done"], 0, None)?;
+        Ok(())
+    }
+}
+
+/// The question and what is done if the answer is yes
+type Step<'a> = (
+    &'a str,
+    &'a dyn Fn(&mut SecureDevice, &mut Transcript) -> Result,
+);
+
+fn serial(device: &mut SecureDevice) -> String {
+    String::from_utf8_lossy(device.proto().serial_num())
+        .trim()
+        .into()
+}
+
+fn describe(device: &mut SecureDevice) -> Result<String> {
+    let state = match device.recv_locking().fix(info!())? {
+        None => "no locking support",
+        Some(flags) if !flags.contains(LockingFlags::LOCKING_ENABLED) => "not set up",
+        Some(flags) if flags.contains(LockingFlags::LOCKED) => "locked",
+        Some(_) => "unlocked",
+    };
+    Ok(format!("{} ({})", serial(device), state))
+}
+
+/// The drive administration menu, to set up the drives
+/// and such without booting anything else
+pub fn run(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    devices: &mut [SecureDevice],
+    config: &Config,
+    own_device: Handle,
+) -> Result {
+    loop {
+        let mut items = Vec::with_capacity(devices.len() + 1);
+        for device in devices.iter_mut() {
+            items.push(describe(device)?);
+        }
+        items.push(String::from("back"));
+        let items = items.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let selected = menu::choose(st, "drive administration:", &items, 0, None)?;
+        let device = match devices.get_mut(selected) {
+            Some(device) => device,
+            None => return Ok(()),
+        };
+
+        let title = format!("drive {}:", items[selected]);
+        let actions = [
+            "set up the drive",
+            "write the PBA image to the shadow MBR",
+            "check the shadow MBR hash",
+            "reset the drive to its factory state",
+            "erase a locking range",
+            "configure a locking range",
+            "manage the users",
+            "reset the ComID or the TPer",
+            "back",
+        ];
+        match menu::choose(st, &title, &actions, 0, None)? {
+            0 => provision(st, greeter, device, config)?,
+            1 => write_pba(st, greeter, device, own_device, config)?,
+            2 => check_mbr(st, device, config)?,
+            3 => reset(st, greeter, device)?,
+            4 => erase_range(st, greeter, device)?,
+            5 => configure_range(st, greeter, device)?,
+            6 => manage_users(st, greeter, device, config)?,
+            7 => reset_tper(st, device)?,
+            _ => {}
+        }
+    }
+}
+
+/// Takes ownership of a drive in its factory state and sets it up the same way
+/// `sedutil-cli --initialsetup` and `--enablelockingrange` would, the new password
+/// is both the SID and the Admin1 one
+fn provision(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    device: &mut SecureDevice,
+    config: &Config,
+) -> Result {
+    let mut transcript = Transcript::new(format!("setting up drive {}", serial(device)));
+    let serial = device.proto().serial_num().to_vec();
+
+    // the ranges to put in the Single User Mode, empty for all of them
+    let sum = device.recv_single_user().fix(info!())?;
+    let single_user = match sum {
+        Some(_) => {
+            let range = format!("only range {} (locking-range)", config.locking_range);
+            let title = format!(
+                "{}\nthe drive supports the Single User Mode, where each range is owned by a \
+                 user and the admins can't unlock it - use it?",
+                transcript.0
+            );
+            match menu::choose(st, &title, &["no", "for all the ranges", &range], 0, None)? {
+                0 => None,
+                1 => Some(Vec::new()),
+                _ => Some(vec![config.locking_range]),
+            }
+        }
+        None => None,
+    };
+    if let (Some(sum), Some(_)) = (sum, &single_user) {
+        transcript.note(&format!(
+            "using the Single User Mode, up to {} ranges",
+            sum.max_ranges
+        ));
+    }
+
+    let password = read_new_password(st, greeter, &config.password_policy)?;
+    let hash = hash_password(&password, &serial);
+
+    let steps: [Step; 5] = [
+        ("take ownership, setting the SID password", &|device, t| {
+            let msid = {
+                let mut session =
+                    OpalSession::start(device, uid::OPAL_ADMINSP, uid::OPAL_ANYBODY, None)?;
+                session.get_pin(uid::OPAL_C_PIN_MSID)?
+            };
+            t.note("read the MSID");
+            let mut session =
+                OpalSession::start(device, uid::OPAL_ADMINSP, uid::OPAL_SID, Some(&msid))?;
+            session.set_pin(uid::OPAL_C_PIN_SID, &hash)?;
+            t.note("set the SID password");
+            Ok(())
+        }),
+        ("activate the Locking SP", &|device, t| {
+            let mut session =
+                OpalSession::start(device, uid::OPAL_ADMINSP, uid::OPAL_SID, Some(&hash))?;
+            match &single_user {
+                Some(ranges) => {
+                    session.activate_single_user(uid::OPAL_LOCKINGSP, ranges)?;
+                    t.note(
+                        "activated the Locking SP, UserN+1 owns range N and has no password yet",
+                    );
+                }
+                None => {
+                    session.activate(uid::OPAL_LOCKINGSP)?;
+                    t.note("activated the Locking SP");
+                }
+            }
+            Ok(())
+        }),
+        ("set the Admin1 password", &|device, t| {
+            // activation makes the Admin1 password the same as the SID one
+            let mut session =
+                OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash))?;
+            session.set_pin(uid::OPAL_C_PIN_ADMIN1, &hash)?;
+            t.note("set the Admin1 password");
+            Ok(())
+        }),
+        ("enable locking of the global range", &|device, t| {
+            let mut session =
+                OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash))?;
+            session.enable_locking_range(0, true)?;
+            session.set_locking_range(0, LockingState::ReadWrite)?;
+            t.note("enabled locking of the global range, it locks on the next power cycle");
+            Ok(())
+        }),
+        (
+            "enable the shadow MBR (the PBA image has to be written to it too)",
+            &|device, t| {
+                let mut session =
+                    OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash))?;
+                session.set_mbr_enable(true)?;
+                // so that the drive itself is not hidden until the next power cycle
+                session.set_mbr_done(true)?;
+                t.note("enabled the shadow MBR");
+                Ok(())
+            },
+        ),
+    ];
+
+    for (question, action) in steps.iter() {
+        if !transcript.step(st, question, |_, t| action(device, t))? {
+            break;
+        }
+    }
+    transcript.show(st)
+}
+
+/// Prints how far along a long operation is, over the last line
+fn progress(st: &mut SystemTable<Boot>, what: &str, done: usize, total: usize) {
+    write!(st.stdout(), "\r{} {}%", what, done * 100 / total.max(1)).unwrap();
+}
+
+/// Writes the PBA image from the greeter partition to the shadow MBR
+/// as `sedutil-cli --loadpbaimage` does, and reads it back to verify
+fn write_pba(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    device: &mut SecureDevice,
+    own_device: Handle,
+    config: &Config,
+) -> Result {
+    let path = config.pba_image.as_deref().unwrap_or("pba.gptdisk");
+    let mut transcript = Transcript::new(format!(
+        "writing {} to the shadow MBR of drive {}",
+        path,
+        serial(device)
+    ));
+
+    let image = match read_file(st, own_device, path).fix(info!())? {
+        Some(image) => image,
+        None => {
+            transcript.note("the image was not found on the greeter partition");
+            return transcript.show(st);
+        }
+    };
+    transcript.note(&format!("read the image, {} KiB", image.len() / 1024));
+
+    let password = greeter.read_secret(st, "Admin1 password: ", None, false)?;
+    let hash = hash_password(&password, device.proto().serial_num());
+
+    let properties = match session::properties(device) {
+        Ok(properties) => properties,
+        Err(e) => {
+            transcript.note(&format!(
+                "could not negotiate the packet sizes ({:?}), using the minimal ones",
+                e
+            ));
+            Properties::default()
+        }
+    };
+    let chunk = properties.chunk_size();
+    transcript.note(&format!("writing in chunks of {} bytes", chunk));
+
+    let question = "write the image? whatever is in the shadow MBR now is overwritten";
+    let written = transcript.step(st, question, |st, t| {
+        let mut session =
+            OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash))?
+                .buffer_size(properties.max_com_packet_size);
+        for (i, data) in image.chunks(chunk).enumerate() {
+            progress(st, "writing..", i * chunk, image.len());
+            session.write_mbr((i * chunk) as u64, data)?;
+        }
+        progress(st, "writing..", image.len(), image.len());
+        t.note("wrote the image");
+        Ok(())
+    })?;
+    if !written {
+        return transcript.show(st);
+    }
+
+    transcript.step(st, "read it back to verify?", |st, t| {
+        let mut session =
+            OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash))?
+                .buffer_size(properties.max_com_packet_size);
+        for (i, expected) in image.chunks(chunk).enumerate() {
+            progress(st, "verifying..", i * chunk, image.len());
+            let offset = i * chunk;
+            if session.read_mbr(offset as u64, expected.len())? != expected {
+                t.note(&format!(
+                    "MISMATCH in the {} bytes at {}",
+                    expected.len(),
+                    offset
+                ));
+                return Ok(());
+            }
+        }
+        progress(st, "verifying..", image.len(), image.len());
+        t.note("verified, the shadow MBR has the image");
+        Ok(())
+    })?;
+    transcript.show(st)
+}
+
+/// Shows the SHA-256 of the shadow MBR, to compare it with the one of the built image
+fn check_mbr(st: &mut SystemTable<Boot>, device: &mut SecureDevice, config: &Config) -> Result {
+    let mut transcript = Transcript::new(format!(
+        "checking the shadow MBR of drive {}",
+        serial(device)
+    ));
+    match config.mbr_sha256_size {
+        Some(size) => transcript.note(&format!("hashing the first {} bytes", size)),
+        None => transcript.note("hashing all of it, set mbr-sha256-size to make it faster"),
+    }
+    write!(st.stdout(), "\nreading..").unwrap();
+    match mbr_sha256(device, config.mbr_sha256_size) {
+        Ok(hash) => {
+            transcript.note(&format!("SHA-256: {}", hex(&hash)));
+            match config.mbr_sha256 {
+                Some(expected) if expected == hash => transcript.note("matches mbr-sha256"),
+                Some(expected) => {
+                    transcript.note(&format!("DOES NOT MATCH mbr-sha256 {}", hex(&expected)))
+                }
+                None => transcript.note("there is no mbr-sha256 in the config to compare with"),
+            }
+        }
+        Err(e) => transcript.note(&format!("could not read it, nothing compared: {:?}", e)),
+    }
+    transcript.show(st)
+}
+
+/// Reverts the drive (or only its Locking SP) to the factory state, the way
+/// to get the drive back when the password is forgotten is the PSID revert
+fn reset(st: &mut SystemTable<Boot>, greeter: &mut Greeter, device: &mut SecureDevice) -> Result {
+    let mut transcript = Transcript::new(format!("resetting drive {}", serial(device)));
+
+    let title = "how to reset it? ALL the data on the drive is destroyed either way";
+    let ways = [
+        "with the PSID printed on the drive label (when the password is forgotten)",
+        "with the SID password",
+        "only the Locking SP, with the Admin1 password",
+        "only the Locking SP, with the Admin1 password, keeping the data of the global range",
+        "back",
+    ];
+    let way = menu::choose(st, title, &ways, ways.len() - 1, None)?;
+    if way >= ways.len() - 1 {
+        return Ok(());
+    }
+    transcript.note(&format!("resetting {}", ways[way]));
+
+    let warning = if way == 3 {
+        "the locking setup and the data of all the other ranges is destroyed, continue?"
+    } else {
+        "EVERYTHING on the drive is destroyed and can't be recovered, continue?"
+    };
+    if !matches!(transcript.confirm(st, warning)?, Answer::Yes) {
+        transcript.note("aborted");
+        return transcript.show(st);
+    }
+
+    let (prompt, authority) = match way {
+        0 => ("PSID: ", uid::OPAL_PSID),
+        1 => ("SID password: ", uid::OPAL_SID),
+        _ => ("Admin1 password: ", uid::OPAL_ADMIN1),
+    };
+    let secret = greeter.read_secret(st, prompt, None, false)?;
+    // sedutil doesn't hash the PSID, it is used as printed
+    let challenge = if way == 0 {
+        secret.trim().as_bytes().to_vec()
+    } else {
+        hash_password(&secret, device.proto().serial_num())
+    };
+
+    transcript.step(st, "LAST CHANCE: really reset the drive?", |_, t| {
+        if way < 2 {
+            let session =
+                OpalSession::start(device, uid::OPAL_ADMINSP, authority, Some(&challenge))?;
+            session.revert(uid::OPAL_ADMINSP)?;
+        } else {
+            let session =
+                OpalSession::start(device, uid::OPAL_LOCKINGSP, authority, Some(&challenge))?;
+            session.revert_sp(way == 3)?;
+        }
+        t.note("the drive is reset to its factory state");
+        Ok(())
+    })?;
+    transcript.show(st)
+}
+
+/// The locking ranges to choose from, the global one and the ones the drive supports
+fn choose_range(
+    st: &mut SystemTable<Boot>,
+    device: &mut SecureDevice,
+    title: &str,
+) -> Result<Option<u8>> {
+    let max_ranges = max_ranges(device);
+    range_menu(st, max_ranges, title)
+}
+
+/// How many locking ranges besides the global one there are, 8 if the drive doesn't tell
+fn max_ranges(device: &mut SecureDevice) -> u8 {
+    OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ANYBODY, None)
+        .and_then(|mut session| session.max_ranges())
+        .unwrap_or_else(|e| {
+            log::warn!("could not read the number of locking ranges: {:?}", e);
+            8
+        })
+        .min(u8::MAX as u64 - 1) as u8
+}
+
+fn range_menu(st: &mut SystemTable<Boot>, max_ranges: u8, title: &str) -> Result<Option<u8>> {
+    let mut items = Vec::with_capacity(max_ranges as usize + 2);
+    items.push(String::from("the global range"));
+    items.extend((1..=max_ranges).map(|range| format!("range {}", range)));
+    items.push(String::from("back"));
+    let items = items.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let range = menu::choose(st, title, &items, 0, None)?;
+    Ok((range <= max_ranges as usize).then_some(range as u8))
+}
+
+/// Cryptographically erases a single locking range, without reverting the whole drive
+fn erase_range(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    device: &mut SecureDevice,
+) -> Result {
+    let range = match choose_range(st, device, "which locking range to erase?")? {
+        Some(range) => range,
+        None => return Ok(()),
+    };
+    let mut transcript = Transcript::new(format!(
+        "erasing locking range {} of drive {}",
+        range,
+        serial(device)
+    ));
+
+    let ways = [
+        "generate a new key for it, as Admin1",
+        "erase it, as its user in the Single User Mode (resets its password too)",
+        "back",
+    ];
+    let erase = match menu::choose(st, "how to erase it?", &ways, 0, None)? {
+        0 => false,
+        1 => true,
+        _ => return Ok(()),
+    };
+    // in the Single User Mode User1 owns the global range, User2 owns range 1 and so on
+    let authority = if erase {
+        Authority::User(range.saturating_add(1))
+    } else {
+        Authority::Admin(1)
+    };
+    let prompt = format!("{} password: ", authority);
+    let password = greeter.read_secret(st, &prompt, None, false)?;
+    let hash = hash_password(&password, device.proto().serial_num());
+
+    let question = "ALL the data in the range is destroyed and can't be recovered, continue?";
+    transcript.step(st, question, |_, t| {
+        let mut session =
+            OpalSession::start(device, uid::OPAL_LOCKINGSP, authority.uid(), Some(&hash))?;
+        if erase {
+            session.erase(range)?;
+            t.note("erased the range");
+        } else {
+            session.gen_key(range)?;
+            t.note("generated a new key for the range");
+        }
+        Ok(())
+    })?;
+    transcript.show(st)
+}
+
+/// Returns the reason the range can't be set up like this, if it can't
+fn check_range(start: u64, length: u64, blocks: u64, geometry: Option<Geometry>) -> Option<String> {
+    if start > blocks || length > blocks - start {
+        return Some(format!("the drive only has {} blocks", blocks));
+    }
+    match geometry {
+        Some(g) if g.align && g.alignment_granularity > 1 => {
+            let granularity = g.alignment_granularity;
+            let aligned =
+                |lba: u64| (lba - g.lowest_aligned_lba).checked_rem(granularity) == Some(0);
+            if start < g.lowest_aligned_lba || !aligned(start) || !aligned(start + length) {
+                return Some(format!(
+                    "the range has to be aligned to {} blocks, starting from block {}",
+                    granularity, g.lowest_aligned_lba
+                ));
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Reads a number of blocks, or None if what was entered is not one
+fn read_blocks(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    prompt: &str,
+) -> Result<Option<u64>> {
+    Ok(greeter.read_text(st, prompt, None)?.trim().parse().ok())
+}
+
+/// Moves, resizes and enables locking of a range, the global one can only be enabled
+fn configure_range(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    device: &mut SecureDevice,
+) -> Result {
+    let range = match choose_range(st, device, "which locking range to configure?")? {
+        Some(range) => range,
+        None => return Ok(()),
+    };
+    let mut transcript = Transcript::new(format!(
+        "configuring locking range {} of drive {}",
+        range,
+        serial(device)
+    ));
+
+    let blockio = st
+        .boot_services()
+        .handle_protocol::<BlockIO>(device.handle())
+        .fix(info!())?;
+    let media = unsafe { &*blockio.get() }.media();
+    let mut blocks = media.last_block() + 1;
+    let geometry = device.recv_geometry().fix(info!())?;
+    transcript.note(&format!(
+        "the drive has {} blocks of {} bytes",
+        blocks,
+        media.block_size()
+    ));
+    match geometry {
+        Some(g) if g.align => transcript.note(&format!(
+            "ranges are aligned to {} blocks, starting from block {}",
+            g.alignment_granularity, g.lowest_aligned_lba
+        )),
+        Some(_) => transcript.note("ranges don't have to be aligned"),
+        None => transcript.note("the drive doesn't report the alignment"),
+    }
+    if let Some(g) = geometry {
+        if g.logical_block_size != 0 && g.logical_block_size != media.block_size() {
+            // the ranges are in the blocks of the drive, not in the ones of BlockIO
+            blocks = blocks * media.block_size() as u64 / g.logical_block_size as u64;
+            transcript.note(&format!(
+                "the ranges are in blocks of {} bytes, the drive has {} of those",
+                g.logical_block_size, blocks
+            ));
+        }
+    }
+
+    let password = greeter.read_secret(st, "Admin1 password: ", None, false)?;
+    let hash = hash_password(&password, device.proto().serial_num());
+    let mut session =
+        match OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash)) {
+            Ok(session) => session,
+            Err(e) => {
+                transcript.note(&format!("could not start the session: {:?}", e));
+                return transcript.show(st);
+            }
+        };
+
+    loop {
+        let state = match session.locking_range(range) {
+            Ok(r) => format!(
+                "starts at block {}, {} blocks long, read locking {}, write locking {}",
+                r.start,
+                r.length,
+                if r.read_lock_enabled { "on" } else { "off" },
+                if r.write_lock_enabled { "on" } else { "off" },
+            ),
+            Err(e) => format!("could not read the range: {:?}", e),
+        };
+        let title = format!("{}\n{}", transcript.0, state);
+        let actions = [
+            "set the start and the length",
+            "enable read and write locking",
+            "enable only write locking",
+            "disable locking",
+            "lock it on power cycles",
+            "don't lock it on power cycles",
+            "done",
+        ];
+        let action = menu::choose(st, &title, &actions, 0, None)?;
+        let result = match action {
+            0 if range == 0 => {
+                transcript.note("the global range covers everything not in other ranges");
+                continue;
+            }
+            0 => {
+                let start = read_blocks(st, greeter, "start block: ")?;
+                let length = read_blocks(st, greeter, "length in blocks: ")?;
+                let (start, length) = match (start, length) {
+                    (Some(start), Some(length)) => (start, length),
+                    _ => {
+                        transcript.note("not a number");
+                        continue;
+                    }
+                };
+                if let Some(reason) = check_range(start, length, blocks, geometry) {
+                    transcript.note(&reason);
+                    continue;
+                }
+                session.set_range(range, start, length)
+            }
+            1 => session.set_lock_enabled(range, true, true),
+            2 => session.set_lock_enabled(range, false, true),
+            3 => session.set_lock_enabled(range, false, false),
+            4 => session.set_lock_on_reset(range, true),
+            5 => session.set_lock_on_reset(range, false),
+            _ => return Ok(()),
+        };
+        match result {
+            Ok(()) => transcript.note(actions[action]),
+            Err(e) => transcript.note(&format!("failed: {:?}", e)),
+        }
+    }
+}
+
+/// Enables the users of the Locking SP and allows them to unlock ranges,
+/// so that e.g. each one can only unlock their own range
+fn manage_users(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    device: &mut SecureDevice,
+    config: &Config,
+) -> Result {
+    let mut transcript = Transcript::new(format!("managing the users of drive {}", serial(device)));
+    let max_ranges = max_ranges(device);
+    let serial = device.proto().serial_num().to_vec();
+
+    let password = greeter.read_secret(st, "Admin1 password: ", None, false)?;
+    let hash = hash_password(&password, &serial);
+    let mut session =
+        match OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ADMIN1, Some(&hash)) {
+            Ok(session) => session,
+            Err(e) => {
+                transcript.note(&format!("could not start the session: {:?}", e));
+                return transcript.show(st);
+            }
+        };
+
+    // there is usually a user per range, plus one for the global range
+    let users = max_ranges.saturating_add(1);
+    loop {
+        let mut items = (1..=users)
+            .map(|n| Authority::User(n).to_string())
+            .collect::<Vec<_>>();
+        items.push(String::from("done"));
+        let items = items.iter().map(String::as_str).collect::<Vec<_>>();
+        let title = format!("{}\nwhich user?", transcript.0);
+        let user = match menu::choose(st, &title, &items, 0, None)? {
+            n if n < users as usize => Authority::User(n as u8 + 1),
+            _ => return transcript.show(st),
+        };
+
+        let title = format!("{}\n{}:", transcript.0, user);
+        let actions = [
+            "enable and set the password",
+            "disable",
+            "allow unlocking a range",
+            "disallow unlocking a range",
+            "back",
+        ];
+        let result = match menu::choose(st, &title, &actions, 0, None)? {
+            0 => {
+                let new = read_new_password(st, greeter, &config.password_policy)?;
+                session
+                    .set_pin(user.c_pin_uid(), &hash_password(&new, &serial))
+                    .and_then(|_| session.enable_authority(user, true))
+                    .map(|_| format!("enabled {} and set the password", user))
+            }
+            1 => session
+                .enable_authority(user, false)
+                .map(|_| format!("disabled {}", user)),
+            action @ (2 | 3) => {
+                let allow = action == 2;
+                let title = if allow {
+                    format!("which range should {} be able to unlock?", user)
+                } else {
+                    format!("which range should {} no longer be able to unlock?", user)
+                };
+                let range = match range_menu(st, max_ranges, &title)? {
+                    Some(range) => range,
+                    None => continue,
+                };
+                // whoever else could unlock it still can
+                session
+                    .allow_ace(locking_range_ace_uid(range, false), user, allow)
+                    .and_then(|_| {
+                        session.allow_ace(locking_range_ace_uid(range, true), user, allow)
+                    })
+                    .map(|_| {
+                        if allow {
+                            format!("{} can unlock range {}", user, range)
+                        } else {
+                            format!("{} can no longer unlock range {}", user, range)
+                        }
+                    })
+            }
+            _ => continue,
+        };
+        match result {
+            Ok(done) => transcript.note(&done),
+            Err(e) => transcript.note(&format!("failed: {:?}", e)),
+        }
+    }
+}
+
+/// Gets the drive out of a stuck state, e.g. when it refuses to start sessions
+fn reset_tper(st: &mut SystemTable<Boot>, device: &mut SecureDevice) -> Result {
+    let mut transcript = Transcript::new(format!("resetting drive {}", serial(device)));
+    transcript.step(
+        st,
+        "reset the ComID, ending all the open sessions?",
+        |_, t| {
+            device.stack_reset().fix(info!())?;
+            t.note("reset the ComID");
+            Ok(())
+        },
+    )?;
+    transcript.step(
+        st,
+        "reset the TPer, as if the drive was power cycled? the ranges get locked",
+        |_, t| {
+            device.tper_reset().fix(info!())?;
+            t.note("reset the TPer");
+            Ok(())
+        },
+    )?;
+    transcript.show(st)
+}
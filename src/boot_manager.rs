@@ -0,0 +1,242 @@
+use alloc::{format, string::String, vec::Vec};
+use core::convert::TryInto;
+use uefi::{
+    prelude::*,
+    proto::{device_path::DevicePath, loaded_image::LoadedImage, media::fs::SimpleFileSystem},
+    table::runtime::VariableVendor,
+};
+
+use crate::{
+    boot_services_ext::BootServicesExt,
+    error::{Error, Result, ResultFixupExt},
+    info, vars,
+};
+
+const LOAD_OPTION_ACTIVE: u32 = 0x00000001;
+
+const END_TYPE: u8 = 0x7F;
+const END_ENTIRE_SUBTYPE: u8 = 0xFF;
+const MEDIA_TYPE: u8 = 0x04;
+const HARD_DRIVE_SUBTYPE: u8 = 0x01;
+
+/// A parsed `Boot####` variable (EFI_LOAD_OPTION)
+struct LoadOption {
+    attributes: u32,
+    description: String,
+    file_path: Vec<u8>,
+    optional_data: Vec<u8>,
+}
+
+impl LoadOption {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let attributes = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?);
+        let file_path_len = u16::from_le_bytes(bytes.get(4..6)?.try_into().ok()?) as usize;
+
+        let mut description = Vec::new();
+        let mut offset = 6;
+        loop {
+            let ch = u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?);
+            offset += 2;
+            if ch == 0 {
+                break;
+            }
+            description.push(ch);
+        }
+        let file_path = bytes.get(offset..offset + file_path_len)?.to_vec();
+        let optional_data = bytes.get(offset + file_path_len..)?.to_vec();
+
+        // make sure the first device path in the list is well-formed
+        device_path_len(&file_path)?;
+
+        Some(Self {
+            attributes,
+            description: String::from_utf16_lossy(&description),
+            file_path,
+            optional_data,
+        })
+    }
+}
+
+/// Walks the nodes from the header of the node at each offset up to and including
+/// the end node, None if a node is malformed or `node_at` runs out of them
+fn device_path_end<'a>(node_at: impl Fn(usize) -> Option<&'a [u8]>) -> Option<usize> {
+    let mut offset = 0;
+    loop {
+        let node = node_at(offset)?;
+        let len = u16::from_le_bytes([node[2], node[3]]) as usize;
+        if len < 4 {
+            return None;
+        }
+        offset += len;
+        if node[0] == END_TYPE && node[1] == END_ENTIRE_SUBTYPE {
+            return Some(offset);
+        }
+    }
+}
+
+/// Length of the first device path in the buffer, including its end node
+fn device_path_len(bytes: &[u8]) -> Option<usize> {
+    device_path_end(|offset| bytes.get(offset..offset + 4))
+}
+
+/// The bytes of a device path up to and including its end node
+unsafe fn device_path_bytes(dp: &DevicePath) -> Option<&[u8]> {
+    let ptr = dp as *const _ as *const u8;
+    let len = device_path_end(|offset| Some(core::slice::from_raw_parts(ptr.add(offset), 4)))?;
+    Some(core::slice::from_raw_parts(ptr, len))
+}
+
+fn as_device_path(bytes: &mut [u8]) -> &mut DevicePath {
+    unsafe { &mut *(bytes.as_mut_ptr() as *mut DevicePath) }
+}
+
+/// Expands a short-form `HD(..)/File(..)` device path into a full one
+/// by finding the partition with the exact same hard drive node
+fn expand_hard_drive_path(st: &SystemTable<Boot>, file_path: &[u8]) -> Option<Vec<u8>> {
+    let hd_len = u16::from_le_bytes([file_path[2], file_path[3]]) as usize;
+    let hd_node = file_path.get(..hd_len)?;
+
+    let bt = st.boot_services();
+    for handle in bt.find_handles::<SimpleFileSystem>().log_warning().ok()? {
+        let dp = match bt.handle_protocol::<DevicePath>(handle).log_warning() {
+            Ok(dp) => dp,
+            Err(_) => continue,
+        };
+        let full = match unsafe { device_path_bytes(&*dp.get()) } {
+            Some(full) => full,
+            None => continue,
+        };
+        // strip the end node and check if the last node is the same HD node
+        let full = &full[..full.len() - 4];
+        if full.ends_with(hd_node) {
+            let mut expanded = full.to_vec();
+            expanded.extend_from_slice(&file_path[hd_len..]);
+            return Some(expanded);
+        }
+    }
+    None
+}
+
+/// Resolves the load option to a full device path that is visible now,
+/// or `None` if it points nowhere or to the greeter's own partition
+fn resolve(st: &SystemTable<Boot>, option: &LoadOption, own_device: Handle) -> Option<Vec<u8>> {
+    let mut file_path = option.file_path[..device_path_len(&option.file_path)?].to_vec();
+
+    if file_path[0] == MEDIA_TYPE && file_path[1] == HARD_DRIVE_SUBTYPE {
+        file_path = expand_hard_drive_path(st, &file_path)?;
+    }
+
+    let mut copy = file_path.clone();
+    let handle = st
+        .boot_services()
+        .locate_device_path::<SimpleFileSystem>(as_device_path(&mut copy))
+        .log_warning()
+        .ok()?;
+
+    (handle != own_device).then_some(file_path)
+}
+
+fn read_u16s(st: &SystemTable<Boot>, name: &str) -> Result<Vec<u16>> {
+    Ok(vars::get(
+        st.runtime_services(),
+        name,
+        &VariableVendor::GLOBAL_VARIABLE,
+    )
+    .fix(info!())?
+    .unwrap_or_default()
+    .chunks_exact(2)
+    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+    .collect())
+}
+
+/// Boots the first active `BootNext`/`BootOrder` load option that resolves
+/// to a currently visible device, skipping the one of the greeter itself.
+///
+/// Works as the firmware boot manager would, but after the drives were unlocked.
+pub fn boot_from_boot_order(
+    image_handle: Handle,
+    st: &mut SystemTable<Boot>,
+    own_device: Handle,
+) -> Result {
+    let mut order = read_u16s(st, "BootNext")?;
+    if !order.is_empty() {
+        // BootNext is a one-shot thing, the firmware would delete it too
+        vars::set(
+            st.runtime_services(),
+            "BootNext",
+            &VariableVendor::GLOBAL_VARIABLE,
+            &[],
+        )
+        .fix(info!())?;
+    }
+    order.extend(read_u16s(st, "BootOrder")?);
+
+    for number in order {
+        let name = format!("Boot{:04X}", number);
+        let option = match vars::get(
+            st.runtime_services(),
+            &name,
+            &VariableVendor::GLOBAL_VARIABLE,
+        )
+        .fix(info!())?
+        .as_deref()
+        .and_then(LoadOption::parse)
+        {
+            Some(option) => option,
+            None => {
+                log::debug!("{} is missing or malformed, skipping", name);
+                continue;
+            }
+        };
+
+        if option.attributes & LOAD_OPTION_ACTIVE == 0 {
+            log::debug!("{} ({}) is not active, skipping", name, option.description);
+            continue;
+        }
+
+        let mut file_path = match resolve(st, &option, own_device) {
+            Some(file_path) => file_path,
+            None => {
+                log::debug!(
+                    "{} ({}) is not bootable now, skipping",
+                    name,
+                    option.description
+                );
+                continue;
+            }
+        };
+
+        log::info!("booting {} ({})", name, option.description);
+
+        let loaded_image_handle = match st.boot_services().load_image(
+            true,
+            image_handle,
+            Some(as_device_path(&mut file_path)),
+            None,
+        ) {
+            Ok(handle) => handle.log(),
+            Err(e) => {
+                log::warn!("failed to load {}: {:?}", name, e.status());
+                continue;
+            }
+        };
+
+        if !option.optional_data.is_empty() {
+            let loaded_image = st
+                .boot_services()
+                .handle_protocol::<LoadedImage>(loaded_image_handle)
+                .fix(info!())?;
+            let loaded_image = unsafe { &mut *loaded_image.get() };
+            // the optional data is leaked, it has to outlive the started image
+            let data = option.optional_data.leak();
+            unsafe { loaded_image.set_load_options(data.as_ptr() as _, data.len() as _) };
+        }
+
+        if let Err(e) = st.boot_services().start_image(loaded_image_handle) {
+            log::warn!("{} failed to start: {:?}", name, e.status());
+            continue;
+        }
+        return Ok(());
+    }
+    Err(Error::NoBootOption)
+}
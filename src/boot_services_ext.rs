@@ -1,10 +1,19 @@
-use core::{mem::MaybeUninit, ptr::null};
-use uefi::{prelude::BootServices, proto::device_path::DevicePath, table::Header, Handle, Status};
+use core::{ffi::c_void, mem::MaybeUninit, ptr::null};
+use uefi::{
+    prelude::BootServices, proto::device_path::DevicePath, table::Header, Guid, Handle, Status,
+};
 
 #[repr(C)]
 struct BootServicesHack {
     header: Header,
-    _ignored: [usize; 22],
+    _ignored: [usize; 13],
+    install_protocol_interface: unsafe extern "efiapi" fn(
+        handle: *mut MaybeUninit<Handle>,
+        protocol: &Guid,
+        interface_type: u32,
+        interface: *mut c_void,
+    ) -> Status,
+    _ignored1: [usize; 8],
     load_image: unsafe extern "efiapi" fn(
         boot_policy: u8,
         parent_image_handle: Handle,
@@ -64,6 +73,27 @@ pub trait BootServicesExt: Sized {
         .into_with_err(|_| ())
     }
 
+    /// Installs a native protocol interface, creating a new handle if none is given.
+    ///
+    /// # Safety
+    /// The interface pointer must point to a valid instance of the protocol identified
+    /// by the given GUID that lives for as long as it stays installed.
+    unsafe fn install_protocol_interface(
+        &self,
+        handle: Option<Handle>,
+        protocol: &Guid,
+        interface: *mut c_void,
+    ) -> uefi::Result<Handle> {
+        let hack = self as *const _ as *const BootServicesHack;
+        let mut handle = match handle {
+            Some(handle) => MaybeUninit::new(handle),
+            None => MaybeUninit::zeroed(),
+        };
+        // 0 is EFI_NATIVE_INTERFACE, the only interface type there is
+        ((*hack).install_protocol_interface)(&mut handle, protocol, 0, interface)
+            .into_with_val(|| handle.assume_init())
+    }
+
     fn load_image(
         &self,
         boot_policy: bool,
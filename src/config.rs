@@ -2,7 +2,15 @@ use alloc::{string::String, vec::Vec};
 use core::str;
 use log::LevelFilter;
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    gfx::Position,
+    greeter::Echo,
+    keyboard::Layout,
+    opal::Authority,
+    partition::PartitionSelector,
+    policy::{Policy, Strength},
+};
 
 fn verbs(text: &str) -> Vec<(&str, &str)> {
     text.lines()
@@ -56,28 +64,214 @@ fn optional(verbs: &[(&str, &str)], verb: &str, joiner: Option<char>) -> Option<
     result
 }
 
+fn list(verbs: &[(&str, &str)], verb: &str) -> Vec<String> {
+    verbs
+        .iter()
+        .filter_map(|(v, arg)| (*v == verb).then_some(String::from(*arg)))
+        .collect()
+}
+
+/// Parses `#rrggbb` colors
+fn color(verbs: &[(&str, &str)], verb: &str) -> Option<[u8; 3]> {
+    let value = optional(verbs, verb, None)?;
+    let hex = value.strip_prefix('#').unwrap_or(&value);
+    let parsed = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() == 6);
+    match parsed {
+        Some(rgb) => Some([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]),
+        None => {
+            log::warn!("bad {} '{}', ignoring", verb, value);
+            None
+        }
+    }
+}
+
 fn required(verbs: &[(&str, &str)], verb: &'static str, joiner: Option<char>) -> Result<String> {
     optional(verbs, verb, joiner).ok_or(Error::ConfigVerbMissing(verb))
 }
 
 #[derive(Debug)]
-pub struct Config {
+pub struct ImageTarget {
     pub image: String,
     pub args: String,
+    pub initrd: Vec<String>,
+    pub partition: Option<PartitionSelector>,
+}
+
+#[derive(Debug)]
+pub enum BootTarget {
+    Image(ImageTarget),
+    /// hand off to the firmware boot manager (`BootNext`/`BootOrder`)
+    BootOrder,
+}
+
+#[derive(Debug)]
+pub struct BootEntry {
+    pub name: String,
+    pub target: BootTarget,
+}
+
+impl BootEntry {
+    fn parse(name: String, verbs: &[(&str, &str)]) -> Result<Self> {
+        if optional(verbs, "firmware-boot", None).as_deref() == Some("on") {
+            return Ok(Self {
+                name,
+                target: BootTarget::BootOrder,
+            });
+        }
+        Ok(Self {
+            name,
+            target: BootTarget::Image(ImageTarget {
+                image: required(verbs, "image", Some('\\'))?,
+                args: optional(verbs, "arg", Some(' ')).unwrap_or_default(),
+                initrd: list(verbs, "initrd"),
+                partition: match optional(verbs, "partition", None) {
+                    Some(p) => Some(
+                        PartitionSelector::parse(&p).ok_or(Error::ConfigBadPartitionSelector(p))?,
+                    ),
+                    None => None,
+                },
+            }),
+        })
+    }
+}
+
+type Section<'a, 'v> = (&'a str, &'v [(&'a str, &'a str)]);
+
+/// Splits the verbs into the top-level ones and named sections
+/// started by `entry <name>` verbs
+fn sections<'a, 'v>(
+    verbs: &'v [(&'a str, &'a str)],
+) -> (&'v [(&'a str, &'a str)], Vec<Section<'a, 'v>>) {
+    let mut starts = verbs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (v, _))| (*v == "entry").then_some(i))
+        .peekable();
+
+    let top_level = &verbs[..starts.peek().copied().unwrap_or(verbs.len())];
+
+    let mut sections = Vec::new();
+    while let Some(start) = starts.next() {
+        let end = starts.peek().copied().unwrap_or(verbs.len());
+        sections.push((verbs[start].1, &verbs[start + 1..end]));
+    }
+    (top_level, sections)
+}
+
+/// Parses a SHA-256 hash in hex
+fn sha256(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+fn password_policy(verbs: &[(&str, &str)]) -> Policy {
+    let mut policy = Policy::default();
+    let number = |verb| {
+        let value = optional(verbs, verb, None)?;
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            log::warn!("bad {} '{}', ignoring", verb, value);
+        }
+        parsed
+    };
+    if let Some(min_length) = number("password-min-length") {
+        policy.min_length = min_length;
+    }
+    if let Some(min_classes) = number("password-min-classes") {
+        policy.min_classes = min_classes;
+    }
+    if let Some(strength) = optional(verbs, "password-min-strength", None) {
+        match Strength::parse(&strength) {
+            Some(strength) => policy.min_strength = strength,
+            None => log::warn!("bad password-min-strength '{}', ignoring", strength),
+        }
+    }
+    policy.denylist = list(verbs, "password-deny")
+        .iter()
+        .map(|p| p.to_lowercase())
+        .collect();
+    policy
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub entries: Vec<BootEntry>,
+    pub default_entry: Option<String>,
+    pub timeout: Option<u64>,
     pub log_level: LevelFilter,
     pub prompt: Option<String>,
     pub retry_prompt: Option<String>,
     pub sed_locked_msg: Option<String>,
+    pub last_attempt_msg: Option<String>,
     pub clear_on_retry: bool,
+    pub password_timeout: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub max_retries_msg: Option<String>,
+    pub graphical: bool,
+    pub background_color: Option<[u8; 3]>,
+    pub background_image: Option<String>,
+    pub box_color: Option<[u8; 3]>,
+    pub text_color: Option<[u8; 3]>,
+    pub accent_color: Option<[u8; 3]>,
+    pub font: Option<String>,
+    pub font_scale: Option<usize>,
+    pub logo: Option<String>,
+    pub logo_position: Option<Position>,
+    pub logo_scale: Option<usize>,
+    pub keyboard_layouts: Vec<&'static Layout>,
+    pub echo: Echo,
+    pub allow_reveal: bool,
+    pub allow_password_change: bool,
+    pub password_policy: Policy,
+    pub password_denylist: Option<String>,
+    /// None means Admin1, or the owner of the range in the Single User Mode
+    pub authority: Option<Authority>,
+    pub locking_range: u8,
+    pub admin_menu: bool,
+    pub pba_image: Option<String>,
+    pub mbr_sha256: Option<[u8; 32]>,
+    pub mbr_sha256_size: Option<u64>,
+    /// `on` for all of the drives, or their serial numbers
+    pub block_sid: Vec<String>,
+    pub block_sid_hardware_reset: bool,
 }
 
 impl Config {
+    /// Whether to block the SID authentication of the drive before booting
+    pub fn blocks_sid(&self, serial: &str) -> bool {
+        self.block_sid.iter().any(|s| s == "on" || s == serial)
+    }
+
     pub fn parse(bytes: &[u8]) -> Result<Self> {
         let verbs = verbs(str::from_utf8(bytes).or(Err(Error::ConfigNonUtf8))?);
-        let args = required(&verbs, "arg", Some(' '))?;
+        let (top_level, sections) = sections(&verbs);
+
+        let mut entries = Vec::with_capacity(sections.len() + 1);
+        // top-level image is an unnamed entry, as it was before entries were a thing
+        if let Some(image) = optional(top_level, "image", Some('\\')) {
+            entries.push(BootEntry::parse(image, top_level)?);
+        } else if optional(top_level, "firmware-boot", None).as_deref() == Some("on") {
+            entries.push(BootEntry::parse("firmware boot order".into(), top_level)?);
+        }
+        for (name, verbs) in sections {
+            entries.push(BootEntry::parse(name.into(), verbs)?);
+        }
         Ok(Self {
-            image: required(&verbs, "image", Some('\\'))?,
-            args,
+            entries,
+            default_entry: optional(&verbs, "default", None),
+            timeout: optional(&verbs, "timeout", None).and_then(|t| match t.parse() {
+                Ok(t) => Some(t),
+                Err(_) => {
+                    log::warn!("bad timeout '{}', ignoring", t);
+                    None
+                }
+            }),
             log_level: match optional(&verbs, "log-level", None).as_deref() {
                 None => LevelFilter::Info,
                 Some("error") => LevelFilter::Error,
@@ -93,7 +287,121 @@ impl Config {
             prompt: optional(&verbs, "prompt", None),
             retry_prompt: optional(&verbs, "retry-prompt", None),
             sed_locked_msg: optional(&verbs, "sed-locked-msg", None),
+            last_attempt_msg: optional(&verbs, "last-attempt-msg", None),
             clear_on_retry: optional(&verbs, "clear-on-retry", None).as_deref() == Some("on"),
+            password_timeout: optional(&verbs, "password-timeout", None).and_then(|t| {
+                match t.parse() {
+                    Ok(t) if t > 0 => Some(t),
+                    _ => {
+                        log::warn!("bad password-timeout '{}', ignoring", t);
+                        None
+                    }
+                }
+            }),
+            max_retries: optional(&verbs, "max-retries", None).and_then(|r| match r.parse() {
+                Ok(r) if r > 0 => Some(r),
+                _ => {
+                    log::warn!("bad max-retries '{}', ignoring", r);
+                    None
+                }
+            }),
+            max_retries_msg: optional(&verbs, "max-retries-msg", None),
+            graphical: match optional(&verbs, "greeter", None).as_deref() {
+                None | Some("text") => false,
+                Some("graphical") => true,
+                Some(x) => {
+                    log::warn!("unknown greeter type '{}', defaulting to text", x);
+                    false
+                }
+            },
+            background_color: color(&verbs, "background-color"),
+            background_image: optional(&verbs, "background-image", Some('\\')),
+            box_color: color(&verbs, "box-color"),
+            text_color: color(&verbs, "text-color"),
+            accent_color: color(&verbs, "accent-color"),
+            font: optional(&verbs, "font", Some('\\')),
+            font_scale: optional(&verbs, "font-scale", None).and_then(|s| match s.parse() {
+                Ok(s) => Some(s),
+                Err(_) => {
+                    log::warn!("bad font-scale '{}', ignoring", s);
+                    None
+                }
+            }),
+            keyboard_layouts: list(&verbs, "keyboard-layout")
+                .iter()
+                .filter_map(|name| {
+                    let layout = Layout::find(name);
+                    if layout.is_none() {
+                        log::warn!("unknown keyboard-layout '{}', ignoring", name);
+                    }
+                    layout
+                })
+                .collect(),
+            echo: match optional(&verbs, "password-echo", None).as_deref() {
+                None | Some("mask") => Echo::Mask,
+                Some("none") => Echo::Hidden,
+                Some(x) => {
+                    log::warn!("unknown password-echo type '{}', defaulting to mask", x);
+                    Echo::Mask
+                }
+            },
+            allow_reveal: optional(&verbs, "allow-reveal", None).as_deref() == Some("on"),
+            allow_password_change: optional(&verbs, "allow-password-change", None).as_deref()
+                == Some("on"),
+            password_policy: password_policy(&verbs),
+            password_denylist: optional(&verbs, "password-denylist", Some('\\')),
+            admin_menu: optional(&verbs, "admin-menu", None).as_deref() == Some("on"),
+            block_sid: list(&verbs, "block-sid"),
+            block_sid_hardware_reset: optional(&verbs, "block-sid-hardware-reset", None).as_deref()
+                == Some("on"),
+            pba_image: optional(&verbs, "pba-image", Some('\\')),
+            mbr_sha256: optional(&verbs, "mbr-sha256", None).and_then(|h| {
+                let hash = sha256(&h);
+                if hash.is_none() {
+                    log::warn!("bad mbr-sha256 '{}', ignoring", h);
+                }
+                hash
+            }),
+            mbr_sha256_size: optional(&verbs, "mbr-sha256-size", None).and_then(|s| {
+                match s.parse() {
+                    Ok(s) if s > 0 => Some(s),
+                    _ => {
+                        log::warn!("bad mbr-sha256-size '{}', ignoring", s);
+                        None
+                    }
+                }
+            }),
+            authority: optional(&verbs, "authority", None).and_then(|a| {
+                let authority = Authority::parse(&a);
+                if authority.is_none() {
+                    log::warn!("bad authority '{}', ignoring", a);
+                }
+                authority
+            }),
+            locking_range: match optional(&verbs, "locking-range", None) {
+                None => 0,
+                Some(r) => r.parse().unwrap_or_else(|_| {
+                    log::warn!("bad locking-range '{}', defaulting to 0", r);
+                    0
+                }),
+            },
+            logo: optional(&verbs, "logo", Some('\\')),
+            logo_position: optional(&verbs, "logo-position", None).and_then(|p| {
+                let position = Position::parse(&p);
+                if position.is_none() {
+                    log::warn!("bad logo-position '{}', ignoring", p);
+                }
+                position
+            }),
+            logo_scale: optional(&verbs, "logo-scale", None).and_then(|s| {
+                match s.trim_end_matches('%').parse() {
+                    Ok(s) => Some(s),
+                    Err(_) => {
+                        log::warn!("bad logo-scale '{}', ignoring", s);
+                        None
+                    }
+                }
+            }),
         })
     }
 }
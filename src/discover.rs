@@ -0,0 +1,248 @@
+use alloc::{format, string::String, vec::Vec};
+use core::str;
+use uefi::{
+    prelude::*,
+    proto::media::{
+        file::{File, FileAttribute, FileMode, FileType},
+        fs::SimpleFileSystem,
+    },
+};
+
+use crate::{
+    config::{BootEntry, BootTarget, ImageTarget},
+    error::{Error, Result},
+    partition::{boot_partitions, PartitionSelector},
+    read_file,
+};
+
+/// Well-known loader locations checked on every partition
+const KNOWN_LOADERS: &[(&str, &str)] = &[
+    ("\\EFI\\systemd\\systemd-bootx64.efi", "systemd-boot"),
+    (
+        "\\EFI\\Microsoft\\Boot\\bootmgfw.efi",
+        "Windows Boot Manager",
+    ),
+    ("\\EFI\\BOOT\\BOOTX64.EFI", "default loader"),
+];
+
+fn open(st: &SystemTable<Boot>, handle: Handle, path: &str) -> Option<FileType> {
+    let sfs = st
+        .boot_services()
+        .handle_protocol::<SimpleFileSystem>(handle)
+        .log_warning()
+        .ok()?;
+    unsafe { &mut *sfs.get() }
+        .open_volume()
+        .log_warning()
+        .ok()?
+        .open(path, FileMode::Read, FileAttribute::empty())
+        .log_warning()
+        .ok()?
+        .into_type()
+        .log_warning()
+        .ok()
+}
+
+fn is_file(st: &SystemTable<Boot>, handle: Handle, path: &str) -> bool {
+    matches!(open(st, handle, path), Some(FileType::Regular(_)))
+}
+
+/// Lists the names of the directory entries and whether they are directories themselves
+fn list_dir(st: &SystemTable<Boot>, handle: Handle, path: &str) -> Vec<(String, bool)> {
+    let mut dir = match open(st, handle, path) {
+        Some(FileType::Dir(dir)) => dir,
+        _ => return Vec::new(),
+    };
+    // u64s to satisfy the FileInfo alignment
+    let mut storage = vec![0u64; 128];
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(storage.as_mut_ptr() as *mut u8, storage.len() * 8)
+    };
+
+    let mut entries = Vec::new();
+    while let Ok(Some(info)) = dir.read_entry(&mut buf[..]).log_warning() {
+        let name = info.file_name().as_string();
+        if name != "." && name != ".." {
+            entries.push((name, info.attribute().contains(FileAttribute::DIRECTORY)));
+        }
+    }
+    entries
+}
+
+fn has_extension(name: &str, ext: &str) -> bool {
+    name.len() > ext.len()
+        && name.is_char_boundary(name.len() - ext.len())
+        && name[name.len() - ext.len()..].eq_ignore_ascii_case(ext)
+}
+
+fn image_entry(name: String, partition: &str, image: String) -> BootEntry {
+    BootEntry {
+        name,
+        target: BootTarget::Image(ImageTarget {
+            image,
+            args: String::new(),
+            initrd: Vec::new(),
+            partition: Some(PartitionSelector::Path(partition.into())),
+        }),
+    }
+}
+
+/// BLS paths are relative to the partition root and use forward slashes
+fn bls_path(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    if path.starts_with('\\') {
+        path
+    } else {
+        format!("\\{}", path)
+    }
+}
+
+/// Parses a Boot Loader Specification type #1 entry
+fn parse_bls_entry(text: &str, partition: &str, fallback_title: &str) -> Option<BootEntry> {
+    let mut title = None;
+    let mut version = None;
+    let mut linux = None;
+    let mut efi = None;
+    let mut initrd = Vec::new();
+    let mut options = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(|c: char| c.is_ascii_whitespace()) {
+            Some((key, value)) => (key, value.trim()),
+            None => continue,
+        };
+        match key {
+            "title" => title = Some(value),
+            "version" => version = Some(value),
+            "linux" => linux = Some(value),
+            "efi" => efi = Some(value),
+            "initrd" => initrd.push(bls_path(value)),
+            "options" => options.push(value),
+            _ => {}
+        }
+    }
+
+    let name = match (title, version) {
+        (Some(title), Some(version)) => format!("{} ({})", title, version),
+        (Some(title), None) => title.into(),
+        _ => fallback_title.into(),
+    };
+    let image = match (efi, linux) {
+        (Some(efi), _) => {
+            initrd.clear();
+            efi
+        }
+        (None, Some(linux)) => linux,
+        _ => return None,
+    };
+
+    Some(BootEntry {
+        name,
+        target: BootTarget::Image(ImageTarget {
+            image: bls_path(image),
+            args: options.join(" "),
+            initrd,
+            partition: Some(PartitionSelector::Path(partition.into())),
+        }),
+    })
+}
+
+fn discover_partition(
+    st: &mut SystemTable<Boot>,
+    handle: Handle,
+    partition: &str,
+    result: &mut Vec<BootEntry>,
+) {
+    // type #1 entries, newest versions usually sort last by file name
+    let mut confs = list_dir(st, handle, "\\loader\\entries")
+        .into_iter()
+        .filter(|(name, is_dir)| !is_dir && has_extension(name, ".conf"))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    confs.sort_unstable_by(|a, b| b.cmp(a));
+    for conf in confs {
+        let path = format!("\\loader\\entries\\{}", conf);
+        let text = match read_file(st, handle, &path).log_warning() {
+            Ok(Some(text)) => text,
+            _ => continue,
+        };
+        match str::from_utf8(&text)
+            .ok()
+            .and_then(|text| parse_bls_entry(text, partition, &conf))
+        {
+            Some(entry) => result.push(entry),
+            None => log::warn!("ignoring malformed boot loader entry {}", path),
+        }
+    }
+
+    // type #2 entries - unified kernel images
+    let mut ukis = list_dir(st, handle, "\\EFI\\Linux")
+        .into_iter()
+        .filter(|(name, is_dir)| !is_dir && has_extension(name, ".efi"))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    ukis.sort_unstable_by(|a, b| b.cmp(a));
+    for uki in ukis {
+        let image = format!("\\EFI\\Linux\\{}", uki);
+        result.push(image_entry(uki, partition, image));
+    }
+
+    for (path, name) in KNOWN_LOADERS {
+        if is_file(st, handle, path) {
+            result.push(image_entry((*name).into(), partition, (*path).into()));
+        }
+    }
+
+    // grub is installed under a distro-specific directory
+    for (dir, is_dir) in list_dir(st, handle, "\\EFI") {
+        let image = format!("\\EFI\\{}\\grubx64.efi", dir);
+        if is_dir && is_file(st, handle, &image) {
+            result.push(image_entry(format!("GRUB ({})", dir), partition, image));
+        }
+    }
+}
+
+/// Scans the ESP and XBOOTLDR partitions for bootloaders and BLS entries.
+///
+/// Only the partitions that are not in `known` (e.g. that appeared after
+/// unlocking the drives) are scanned, unless there are none such.
+pub fn discover(
+    st: &mut SystemTable<Boot>,
+    own_device: Handle,
+    known: &[Handle],
+) -> Result<Vec<BootEntry>> {
+    let partitions = boot_partitions(st, own_device)?;
+    let new = partitions
+        .iter()
+        .filter(|(handle, _)| !known.contains(handle))
+        .collect::<Vec<_>>();
+    let partitions = if new.is_empty() {
+        partitions.iter().collect()
+    } else {
+        new
+    };
+
+    let mut result = Vec::new();
+    for (handle, path) in partitions {
+        discover_partition(st, *handle, path, &mut result);
+    }
+
+    // the names have to be unique for the last choice to be remembered
+    let names = result.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    for (i, entry) in result.iter_mut().enumerate() {
+        let duplicates = names[..i].iter().filter(|&n| *n == entry.name).count();
+        if duplicates != 0 {
+            entry.name = format!("{} #{}", entry.name, duplicates + 1);
+        }
+    }
+
+    if result.is_empty() {
+        return Err(Error::NoBootEntries);
+    }
+    log::debug!("discovered entries = {:#?}", result);
+    Ok(result)
+}
@@ -8,6 +8,7 @@ pub type Result<T = ()> = core::result::Result<T, Error>;
 pub enum OpalError {
     Status(StatusCode),
     NoMethodStatus,
+    UnexpectedResponse,
 }
 
 const UNKNOWN: &str = "";
@@ -20,10 +21,13 @@ pub enum Error {
     ConfigNonUtf8,
     ConfigArgsBadUtf16,
     ConfigVerbMissing(&'static str),
+    ConfigBadPartitionSelector(String),
     NoBootPartitions,
-    MultipleBootPartitions,
+    NoBootOption,
+    NoBootEntries,
     ImageNotFound(String),
     ImageNotPeCoff,
+    InitrdNotFound(String),
 }
 
 impl From<Status> for Error {
@@ -0,0 +1,62 @@
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use crate::gfx::{u32_le_at, Color, Image, MAX_IMAGE_SIZE};
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Decodes an uncompressed 24 or 32 bit BMP image
+pub fn decode(data: &[u8]) -> Option<Image> {
+    if data.get(0..2)? != b"BM" {
+        return None;
+    }
+    let pixels_offset = u32_le_at(data, 10)? as usize;
+    let width = u32_le_at(data, 18)? as i32;
+    let height = u32_le_at(data, 22)? as i32;
+    let bpp = u16_at(data, 28)?;
+    let compression = u32_le_at(data, 30)?;
+
+    // 0 is BI_RGB, 3 is BI_BITFIELDS which is always BGRA in practice
+    if width <= 0
+        || height == 0
+        || !(bpp == 24 || bpp == 32)
+        || !(compression == 0 || compression == 3)
+    {
+        return None;
+    }
+    let width = width as usize;
+    // negative height means the rows are stored top-down
+    let top_down = height < 0;
+    let height = height.unsigned_abs() as usize;
+    if width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+        return None;
+    }
+
+    let bytes_pp = bpp as usize / 8;
+    // rows are padded to 4 bytes
+    let stride = (width.checked_mul(bytes_pp)? + 3) & !3;
+    if pixels_offset.checked_add(stride.checked_mul(height)?)? > data.len() {
+        return None;
+    }
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let src_row = if top_down { row } else { height - 1 - row };
+        let start = pixels_offset + src_row * stride;
+        let row = data.get(start..start + width * bytes_pp)?;
+        pixels.extend(
+            row.chunks_exact(bytes_pp)
+                .map(|px| Color::new(px[2], px[1], px[0])),
+        );
+    }
+    Some(Image {
+        width,
+        height,
+        pixels,
+        alpha: None,
+    })
+}
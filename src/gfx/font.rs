@@ -0,0 +1,143 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::str;
+
+use crate::gfx::u32_le_at;
+
+/// The public domain misc-fixed 10x20 font, Latin-1 glyphs only
+static EMBEDDED: &[u8] = include_bytes!("../../assets/font.psf");
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TAB: u8 = 0x02;
+const PSF1_MODE_SEQ: u8 = 0x04;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+
+/// A PC Screen Font (version 1 or 2) bitmap font
+pub struct Font {
+    pub width: usize,
+    pub height: usize,
+    bytes_per_row: usize,
+    glyph_size: usize,
+    glyphs: Vec<u8>,
+    /// empty if the glyphs are indexed by the codepoints directly
+    unicode: BTreeMap<char, usize>,
+}
+
+impl Font {
+    pub fn embedded() -> Self {
+        Self::parse(EMBEDDED).expect("embedded font is valid")
+    }
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.get(0..4)? == PSF2_MAGIC {
+            Self::parse_psf2(data)
+        } else if data.get(0..2)? == PSF1_MAGIC {
+            Self::parse_psf1(data)
+        } else {
+            None
+        }
+    }
+
+    fn parse_psf1(data: &[u8]) -> Option<Self> {
+        let mode = *data.get(2)?;
+        let height = *data.get(3)? as usize;
+        if height == 0 {
+            return None;
+        }
+        let count = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+        let glyphs = data.get(4..4 + count * height)?.to_vec();
+
+        let mut unicode = BTreeMap::new();
+        if mode & (PSF1_MODE_HAS_TAB | PSF1_MODE_SEQ) != 0 {
+            let table = &data[4 + count * height..];
+            let mut glyph = 0;
+            let mut in_sequence = false;
+            for entry in table.chunks_exact(2) {
+                match u16::from_le_bytes([entry[0], entry[1]]) {
+                    0xFFFF => {
+                        glyph += 1;
+                        in_sequence = false;
+                    }
+                    // combining sequences are not supported, skip them
+                    0xFFFE => in_sequence = true,
+                    c if !in_sequence => {
+                        if let Some(c) = char::from_u32(c as u32) {
+                            unicode.entry(c).or_insert(glyph);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Some(Self {
+            width: 8,
+            height,
+            bytes_per_row: 1,
+            glyph_size: height,
+            glyphs,
+            unicode,
+        })
+    }
+
+    fn parse_psf2(data: &[u8]) -> Option<Self> {
+        let header_size = u32_le_at(data, 8)? as usize;
+        let flags = u32_le_at(data, 12)?;
+        let count = u32_le_at(data, 16)? as usize;
+        let glyph_size = u32_le_at(data, 20)? as usize;
+        let height = u32_le_at(data, 24)? as usize;
+        let width = u32_le_at(data, 28)? as usize;
+
+        let bytes_per_row = (width + 7) / 8;
+        if width == 0 || height == 0 || glyph_size < bytes_per_row * height {
+            return None;
+        }
+        let glyphs_end = header_size.checked_add(count.checked_mul(glyph_size)?)?;
+        let glyphs = data.get(header_size..glyphs_end)?.to_vec();
+
+        let mut unicode = BTreeMap::new();
+        if flags & PSF2_HAS_UNICODE_TABLE != 0 {
+            // utf-8 strings, 0xFE starts a sequence and 0xFF ends the glyph entry
+            for (glyph, entry) in data[glyphs_end..].split(|&b| b == 0xFF).enumerate() {
+                let singles = entry.split(|&b| b == 0xFE).next().unwrap_or_default();
+                for c in str::from_utf8(singles).ok()?.chars() {
+                    unicode.entry(c).or_insert(glyph);
+                }
+            }
+        }
+
+        Some(Self {
+            width,
+            height,
+            bytes_per_row,
+            glyph_size,
+            glyphs,
+            unicode,
+        })
+    }
+
+    fn glyph_index(&self, c: char) -> Option<usize> {
+        let index = if self.unicode.is_empty() {
+            c as usize
+        } else {
+            *self.unicode.get(&c)?
+        };
+        (index < self.glyphs.len() / self.glyph_size).then_some(index)
+    }
+
+    /// The bitmap of the glyph, `?` is used for the missing ones
+    pub fn glyph(&self, c: char) -> Option<&[u8]> {
+        let index = self.glyph_index(c).or_else(|| self.glyph_index('?'))?;
+        Some(&self.glyphs[index * self.glyph_size..(index + 1) * self.glyph_size])
+    }
+
+    pub fn is_set(&self, glyph: &[u8], x: usize, y: usize) -> bool {
+        glyph[y * self.bytes_per_row + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    pub fn text_width(&self, text: &str, scale: usize) -> usize {
+        text.chars().count() * self.width * scale
+    }
+}
@@ -0,0 +1,230 @@
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use uefi::{
+    prelude::*,
+    proto::console::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput},
+};
+
+use self::font::Font;
+
+pub mod bmp;
+pub mod font;
+pub mod png;
+
+pub type Color = BltPixel;
+
+/// Images larger than this in either dimension are rejected before anything
+/// is allocated for them, that's more than any screen
+pub const MAX_IMAGE_SIZE: usize = 16384;
+
+/// Little-endian u32 for the BMP and PSF headers
+fn u32_le_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Big-endian u32 for the PNG chunks
+fn u32_be_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+pub fn color([r, g, b]: [u8; 3]) -> Color {
+    BltPixel::new(r, g, b)
+}
+
+/// Where an image is drawn on the screen
+#[derive(Debug, Clone, Copy)]
+pub enum Position {
+    /// horizontally centered above the password box
+    Top,
+    Center,
+    /// horizontally centered below the password box
+    Bottom,
+    /// the top left corner in pixels
+    At(usize, usize),
+}
+
+impl Position {
+    pub fn parse(position: &str) -> Option<Self> {
+        Some(match position {
+            "top" => Self::Top,
+            "center" => Self::Center,
+            "bottom" => Self::Bottom,
+            _ => {
+                let (x, y) = position.split_once(',')?;
+                Self::At(x.trim().parse().ok()?, y.trim().parse().ok()?)
+            }
+        })
+    }
+}
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+    /// `None` for opaque images
+    pub alpha: Option<Vec<u8>>,
+}
+
+impl Image {
+    /// Decodes a BMP or a PNG image, depending on the magic
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        match data.get(0..2)? {
+            b"BM" => bmp::decode(data),
+            _ => png::decode(data),
+        }
+    }
+
+    /// Nearest-neighbour scaling to the given size
+    pub fn scaled(&self, width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+        let mut alpha = self
+            .alpha
+            .as_ref()
+            .map(|_| Vec::with_capacity(width * height));
+        for y in 0..height {
+            let src_y = y * self.height / height;
+            for x in 0..width {
+                let src = src_y * self.width + x * self.width / width;
+                pixels.push(self.pixels[src]);
+                if let (Some(alpha), Some(src_alpha)) = (&mut alpha, &self.alpha) {
+                    alpha.push(src_alpha[src]);
+                }
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+            alpha,
+        }
+    }
+}
+
+fn blend(under: Color, over: Color, alpha: u8) -> Color {
+    let mix =
+        |u: u8, o: u8| ((u as u16 * (255 - alpha as u16) + o as u16 * alpha as u16) / 255) as u8;
+    Color::new(
+        mix(under.red, over.red),
+        mix(under.green, over.green),
+        mix(under.blue, over.blue),
+    )
+}
+
+/// A back buffer that is blitted onto the GOP framebuffer on flushes
+pub struct Framebuffer {
+    gop: *mut GraphicsOutput<'static>,
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    /// Returns `None` if there is no GOP, e.g. on a serial console
+    pub fn new(st: &SystemTable<Boot>) -> Option<Self> {
+        let gop = st
+            .boot_services()
+            .locate_protocol::<GraphicsOutput>()
+            .log_warning()
+            .ok()?;
+        let gop = gop.get() as *mut GraphicsOutput<'static>;
+        let (width, height) = unsafe { &*gop }.current_mode_info().resolution();
+        Some(Self {
+            gop,
+            width,
+            height,
+            pixels: vec![BltPixel::new(0, 0, 0); width * height],
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn fill(&mut self, x: usize, y: usize, w: usize, h: usize, color: Color) {
+        let x_end = (x + w).min(self.width);
+        let y_end = (y + h).min(self.height);
+        for row in y.min(y_end)..y_end {
+            self.pixels[row * self.width + x.min(x_end)..row * self.width + x_end].fill(color);
+        }
+    }
+
+    /// Draws the image with its top left corner at the given, possibly negative, position
+    pub fn draw_image(&mut self, x: isize, y: isize, image: &Image) {
+        for row in 0..image.height {
+            let dst_y = y + row as isize;
+            if dst_y < 0 || dst_y >= self.height as isize {
+                continue;
+            }
+            for col in 0..image.width {
+                let dst_x = x + col as isize;
+                if dst_x < 0 || dst_x >= self.width as isize {
+                    continue;
+                }
+                let src = row * image.width + col;
+                let dst = &mut self.pixels[dst_y as usize * self.width + dst_x as usize];
+                *dst = match &image.alpha {
+                    Some(alpha) => blend(*dst, image.pixels[src], alpha[src]),
+                    None => image.pixels[src],
+                };
+            }
+        }
+    }
+
+    /// Draws the text with its top left corner at the given position,
+    /// each font pixel becoming a `scale` by `scale` square
+    pub fn draw_text(
+        &mut self,
+        font: &Font,
+        x: usize,
+        y: usize,
+        scale: usize,
+        text: &str,
+        color: Color,
+    ) {
+        for (i, c) in text.chars().enumerate() {
+            let glyph = match font.glyph(c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            let glyph_x = x + i * font.width * scale;
+            for row in 0..font.height {
+                for col in 0..font.width {
+                    if font.is_set(glyph, col, row) {
+                        self.fill(glyph_x + col * scale, y + row * scale, scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> uefi::Result {
+        self.flush_rect(0, 0, self.width, self.height)
+    }
+
+    pub fn flush_rect(&mut self, x: usize, y: usize, w: usize, h: usize) -> uefi::Result {
+        let w = w.min(self.width.saturating_sub(x));
+        let h = h.min(self.height.saturating_sub(y));
+        if w == 0 || h == 0 {
+            return Ok(().into());
+        }
+        unsafe { &mut *self.gop }.blt(BltOp::BufferToVideo {
+            buffer: &self.pixels,
+            src: BltRegion::SubRectangle {
+                coords: (x, y),
+                px_stride: self.width,
+            },
+            dest: (x, y),
+            dims: (w, h),
+        })
+    }
+}
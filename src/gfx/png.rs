@@ -0,0 +1,190 @@
+use alloc::vec::Vec;
+
+use crate::gfx::{u32_be_at, Color, Image, MAX_IMAGE_SIZE};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+const GRAY: u8 = 0;
+const RGB: u8 = 2;
+const PALETTE: u8 = 3;
+const GRAY_ALPHA: u8 = 4;
+const RGBA: u8 = 6;
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reverses the per-scanline filters, returning the raw rows
+fn unfilter(data: &[u8], stride: usize, bpp: usize, height: usize) -> Option<Vec<u8>> {
+    // each line is prefixed with its filter type
+    if data.len() < (stride + 1).checked_mul(height)? {
+        return None;
+    }
+    let mut result = vec![0; stride.checked_mul(height)?];
+    for y in 0..height {
+        let line = data.get(y * (stride + 1)..(y + 1) * (stride + 1))?;
+        let (filter, line) = (line[0], &line[1..]);
+        let (done, rest) = result.split_at_mut(y * stride);
+        let prev = if y == 0 {
+            None
+        } else {
+            Some(&done[(y - 1) * stride..])
+        };
+        let row = &mut rest[..stride];
+        for x in 0..stride {
+            let a = if x >= bpp { row[x - bpp] } else { 0 };
+            let b = prev.map_or(0, |p| p[x]);
+            let c = if x >= bpp {
+                prev.map_or(0, |p| p[x - bpp])
+            } else {
+                0
+            };
+            row[x] = match filter {
+                0 => line[x],
+                1 => line[x].wrapping_add(a),
+                2 => line[x].wrapping_add(b),
+                3 => line[x].wrapping_add(((a as u16 + b as u16) / 2) as u8),
+                4 => line[x].wrapping_add(paeth(a, b, c)),
+                _ => return None,
+            };
+        }
+    }
+    Some(result)
+}
+
+/// Decodes a non-interlaced PNG image of any color type
+pub fn decode(data: &[u8]) -> Option<Image> {
+    if data.get(0..8)? != SIGNATURE {
+        return None;
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    let mut offset = 8;
+    loop {
+        let len = u32_be_at(data, offset)? as usize;
+        let kind = data.get(offset + 4..offset + 8)?;
+        let chunk = data.get(offset + 8..(offset + 8).checked_add(len)?)?;
+        match kind {
+            b"IHDR" => header = Some(chunk),
+            b"PLTE" => palette = chunk,
+            b"tRNS" => transparency = chunk,
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+        // skipping the crc too
+        offset += 12 + len;
+    }
+
+    let header = header?;
+    let width = u32_be_at(header, 0)? as usize;
+    let height = u32_be_at(header, 4)? as usize;
+    let depth = *header.get(8)? as usize;
+    let color_type = *header.get(9)?;
+    let interlace = *header.get(12)?;
+    if width == 0
+        || height == 0
+        || width > MAX_IMAGE_SIZE
+        || height > MAX_IMAGE_SIZE
+        || interlace != 0
+    {
+        return None;
+    }
+
+    let channels = match color_type {
+        GRAY | PALETTE => 1,
+        GRAY_ALPHA => 2,
+        RGB => 3,
+        RGBA => 4,
+        _ => return None,
+    };
+    let bits_pp = channels * depth;
+    let stride = (width.checked_mul(bits_pp)? + 7) / 8;
+    let bpp = ((bits_pp + 7) / 8).max(1);
+
+    // nothing past the filtered lines is needed, so no inflating a zip bomb
+    let size = (stride + 1).checked_mul(height)?;
+    let inflated =
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, size).ok()?;
+    let raw = unfilter(&inflated, stride, bpp, height)?;
+
+    // samples are scaled to 8 bits, 16 bit ones just lose the low byte
+    let sample = |row: &[u8], x: usize, channel: usize| -> Option<u8> {
+        let index = x * channels + channel;
+        Some(match depth {
+            8 => *row.get(index)?,
+            16 => *row.get(index * 2)?,
+            1 | 2 | 4 => {
+                let bit = index * depth;
+                let value = (row.get(bit / 8)? >> (8 - depth - bit % 8)) & ((1 << depth) - 1);
+                if color_type == PALETTE {
+                    value
+                } else {
+                    (value as usize * 255 / ((1 << depth) - 1)) as u8
+                }
+            }
+            _ => return None,
+        })
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut alpha = Vec::with_capacity(width * height);
+    for row in raw.chunks_exact(stride) {
+        for x in 0..width {
+            let (r, g, b, a) = match color_type {
+                GRAY => {
+                    let v = sample(row, x, 0)?;
+                    (v, v, v, 255)
+                }
+                GRAY_ALPHA => {
+                    let v = sample(row, x, 0)?;
+                    (v, v, v, sample(row, x, 1)?)
+                }
+                RGB => (
+                    sample(row, x, 0)?,
+                    sample(row, x, 1)?,
+                    sample(row, x, 2)?,
+                    255,
+                ),
+                RGBA => (
+                    sample(row, x, 0)?,
+                    sample(row, x, 1)?,
+                    sample(row, x, 2)?,
+                    sample(row, x, 3)?,
+                ),
+                _ => {
+                    let index = sample(row, x, 0)? as usize;
+                    let rgb = palette.get(index * 3..index * 3 + 3)?;
+                    let a = transparency.get(index).copied().unwrap_or(255);
+                    (rgb[0], rgb[1], rgb[2], a)
+                }
+            };
+            pixels.push(Color::new(r, g, b));
+            alpha.push(a);
+        }
+    }
+
+    let opaque = alpha.iter().all(|&a| a == 255);
+    Some(Image {
+        width,
+        height,
+        pixels,
+        alpha: (!opaque).then_some(alpha),
+    })
+}
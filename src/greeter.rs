@@ -0,0 +1,703 @@
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+use uefi::{prelude::*, proto::console::text::ScanCode, table::runtime::ResetType};
+
+use crate::{
+    config::Config,
+    error::{Result, ResultFixupExt},
+    gfx::{self, font::Font, Color, Framebuffer, Image, Position, MAX_IMAGE_SIZE},
+    info,
+    keyboard::{Key, Keyboard},
+    policy::Strength,
+    read_file,
+    text_input_ex::ShiftState,
+    util::Timer,
+};
+
+const BOX_WIDTH: usize = 400;
+const BOX_HEIGHT: usize = 40;
+const BORDER: usize = 2;
+const DOT_SIZE: usize = 10;
+const DOT_SPACING: usize = 18;
+/// between the box and the text above and below it
+const MARGIN: usize = 12;
+/// how long before the idle timeout runs out the countdown is shown
+const COUNTDOWN_SECS: u64 = 60;
+
+const DEFAULT_BACKGROUND: [u8; 3] = [0x00, 0x00, 0x00];
+const DEFAULT_BOX: [u8; 3] = [0x20, 0x20, 0x20];
+const DEFAULT_TEXT: [u8; 3] = [0xee, 0xee, 0xee];
+const DEFAULT_ACCENT: [u8; 3] = [0xaa, 0xaa, 0xaa];
+
+struct Theme {
+    background: Color,
+    box_color: Color,
+    text: Color,
+    /// the box border and the messages
+    accent: Color,
+}
+
+impl Theme {
+    fn new(config: &Config) -> Self {
+        let color = |c: Option<[u8; 3]>, default| gfx::color(c.unwrap_or(default));
+        Self {
+            background: color(config.background_color, DEFAULT_BACKGROUND),
+            box_color: color(config.box_color, DEFAULT_BOX),
+            text: color(config.text_color, DEFAULT_TEXT),
+            accent: color(config.accent_color, DEFAULT_ACCENT),
+        }
+    }
+}
+
+fn load_font(st: &mut SystemTable<Boot>, own_device: Handle, path: &str) -> Option<Font> {
+    match read_file(st, own_device, path).log_warning() {
+        Ok(Some(data)) => {
+            let font = Font::parse(&data);
+            if font.is_none() {
+                log::warn!("font {} is not a PSF font", path);
+            }
+            font
+        }
+        _ => {
+            log::warn!("font {} not found", path);
+            None
+        }
+    }
+}
+
+fn load_image(st: &mut SystemTable<Boot>, own_device: Handle, path: &str) -> Option<Image> {
+    match read_file(st, own_device, path).log_warning() {
+        Ok(Some(data)) => {
+            let image = Image::decode(&data);
+            if image.is_none() {
+                log::warn!("image {} is not a supported BMP or PNG", path);
+            }
+            image
+        }
+        _ => {
+            log::warn!("image {} not found", path);
+            None
+        }
+    }
+}
+
+/// The GOP part of the greeter
+struct Screen {
+    fb: Framebuffer,
+    theme: Theme,
+    image: Option<Image>,
+    logo: Option<(Image, Position)>,
+    font: Font,
+    scale: usize,
+}
+
+impl Screen {
+    fn new(st: &mut SystemTable<Boot>, config: &Config, own_device: Handle) -> Option<Self> {
+        let fb = Framebuffer::new(st)?;
+
+        let image = config
+            .background_image
+            .as_deref()
+            .and_then(|path| load_image(st, own_device, path));
+
+        let font = config
+            .font
+            .as_deref()
+            .and_then(|path| load_font(st, own_device, path))
+            .unwrap_or_else(Font::embedded);
+
+        // everything is designed for 1080p, so scale it up on HiDPI screens,
+        // but never beyond the password box fitting on the screen
+        let max_scale = (fb.width() / BOX_WIDTH)
+            .min(fb.height() / BOX_HEIGHT)
+            .max(1);
+        let scale = config
+            .font_scale
+            .unwrap_or_else(|| fb.height() / 1080)
+            .clamp(1, max_scale);
+
+        // the logo follows the HiDPI scale unless told otherwise
+        let logo_scale = config.logo_scale.unwrap_or(scale * 100);
+        let logo = config.logo.as_deref().and_then(|path| {
+            let logo = load_image(st, own_device, path)?;
+            // never bigger than the screen, which keeps it within the image size limit too
+            let fit = (fb.width().min(MAX_IMAGE_SIZE) * 100 / logo.width.max(1))
+                .min(fb.height().min(MAX_IMAGE_SIZE) * 100 / logo.height.max(1));
+            let logo = match logo_scale.min(fit) {
+                100 => logo,
+                logo_scale => logo.scaled(
+                    logo.width * logo_scale / 100,
+                    logo.height * logo_scale / 100,
+                ),
+            };
+            Some((logo, config.logo_position.unwrap_or(Position::Top)))
+        });
+
+        Some(Self {
+            fb,
+            theme: Theme::new(config),
+            image,
+            logo,
+            font,
+            scale,
+        })
+    }
+
+    fn box_rect(&self) -> (usize, usize, usize, usize) {
+        let w = (BOX_WIDTH * self.scale).min(self.fb.width());
+        let h = BOX_HEIGHT * self.scale;
+        let x = (self.fb.width() - w) / 2;
+        let y = self.fb.height().saturating_sub(h) / 2;
+        (x, y, w, h)
+    }
+
+    fn text_height(&self) -> usize {
+        self.font.height * self.scale
+    }
+
+    fn draw_background(&mut self) -> uefi::Result {
+        let (w, h) = (self.fb.width(), self.fb.height());
+        self.fb.fill(0, 0, w, h, self.theme.background);
+        if let Some(image) = &self.image {
+            // centered and cropped, no scaling
+            let x = (w as isize - image.width as isize) / 2;
+            let y = (h as isize - image.height as isize) / 2;
+            self.fb.draw_image(x, y, image);
+        }
+        if let Some((logo, position)) = &self.logo {
+            let (x, y) = self.logo_position(logo, *position);
+            self.fb.draw_image(x as isize, y as isize, logo);
+        }
+        self.fb.flush()
+    }
+
+    fn logo_position(&self, logo: &Image, position: Position) -> (usize, usize) {
+        let (w, h) = (self.fb.width(), self.fb.height());
+        let (_, box_y, _, box_h) = self.box_rect();
+        let centered_x = w.saturating_sub(logo.width) / 2;
+        match position {
+            Position::Top => {
+                // the space above the prompt
+                let space = box_y.saturating_sub(self.text_height() + MARGIN * self.scale);
+                (centered_x, space.saturating_sub(logo.height) / 2)
+            }
+            Position::Center => (centered_x, h.saturating_sub(logo.height) / 2),
+            Position::Bottom => {
+                // the space below the messages
+                let start = box_y + box_h + MARGIN * self.scale + self.text_height();
+                let space = h.saturating_sub(start);
+                (centered_x, start + space.saturating_sub(logo.height) / 2)
+            }
+            Position::At(x, y) => (x, y),
+        }
+    }
+
+    /// Draws the box with its content, the cursor before the `cursor`th
+    /// character and the indicator text at its right end
+    fn draw_box(&mut self, content: Content, cursor: usize, indicator: &str) -> uefi::Result {
+        let (x, y, w, h) = self.box_rect();
+        let border = BORDER * self.scale;
+        let (dot_size, dot_spacing) = (DOT_SIZE * self.scale, DOT_SPACING * self.scale);
+
+        self.fb.fill(x, y, w, h, self.theme.accent);
+        self.fb.fill(
+            x + border,
+            y + border,
+            w.saturating_sub(2 * border),
+            h.saturating_sub(2 * border),
+            self.theme.box_color,
+        );
+
+        let indicator_w = self.font.text_width(indicator, self.scale);
+        let text_y = y + h.saturating_sub(self.text_height()) / 2;
+        if !indicator.is_empty() {
+            let text_x = (x + w).saturating_sub(border + dot_spacing / 2 + indicator_w);
+            let color = self.theme.accent;
+            self.fb
+                .draw_text(&self.font, text_x, text_y, self.scale, indicator, color);
+        }
+
+        let space = w
+            .saturating_sub(2 * border)
+            .saturating_sub(indicator_w + dot_spacing);
+        let start_x = x + border + (dot_spacing - dot_size) / 2;
+        let (len, cell, first) = match content {
+            // the dots are not scrolled, just the ones that fit are shown
+            Content::Dots(len) => {
+                let dot_y = y + (h - dot_size) / 2;
+                for i in 0..len.min(space / dot_spacing) {
+                    let dot_x = start_x + i * dot_spacing;
+                    self.fb
+                        .fill(dot_x, dot_y, dot_size, dot_size, self.theme.text);
+                }
+                (len, dot_spacing, 0)
+            }
+            // while the text is scrolled to keep the cursor visible
+            Content::Text(chars) => {
+                let cell = self.font.width * self.scale;
+                let first = cursor.saturating_sub(space / cell);
+                let end = chars.len().min(first + space / cell);
+                let text = chars[first..end].iter().collect::<String>();
+                let color = self.theme.text;
+                self.fb
+                    .draw_text(&self.font, start_x, text_y, self.scale, &text, color);
+                (chars.len(), cell, first)
+            }
+        };
+
+        // no need for the cursor when it's at the end
+        if cursor < len && (cursor - first) * cell <= space {
+            let caret_x = start_x + (cursor - first) * cell;
+            let caret_h = self.text_height();
+            self.fb.fill(
+                caret_x.saturating_sub(self.scale),
+                text_y,
+                self.scale,
+                caret_h,
+                self.theme.accent,
+            );
+        }
+        self.fb.flush_rect(x, y, w, h)
+    }
+
+    /// Draws the text horizontally centered, it's not cleared until the next prompt
+    fn draw_text(&mut self, y: usize, text: &str, color: Color) -> uefi::Result {
+        let w = self.font.text_width(text, self.scale).min(self.fb.width());
+        let x = (self.fb.width() - w) / 2;
+        self.fb.draw_text(&self.font, x, y, self.scale, text, color);
+        self.fb.flush_rect(x, y, w, self.text_height())
+    }
+
+    fn draw_prompt(&mut self, prompt: &str) -> uefi::Result {
+        let (_, y, _, _) = self.box_rect();
+        let y = y.saturating_sub(self.text_height() + MARGIN * self.scale);
+        // trailing spaces are there for the text console
+        self.draw_text(y, prompt.trim_end(), self.theme.text)
+    }
+
+    fn draw_message(&mut self, message: &str) -> uefi::Result {
+        let (_, y, _, h) = self.box_rect();
+        self.draw_text(y + h + MARGIN * self.scale, message, self.theme.accent)
+    }
+}
+
+/// What the password box shows
+enum Content<'a> {
+    Dots(usize),
+    Text(&'a [char]),
+}
+
+/// How the typed password is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Echo {
+    /// dots or asterisks
+    Mask,
+    /// nothing at all, not even the length
+    Hidden,
+}
+
+/// What was entered at the password prompt
+pub enum Entered {
+    /// with enter, to unlock
+    Password(String),
+    /// with F3, the current password to change
+    ChangePassword(String),
+    /// F12 was pressed to get to the drive administration
+    AdminMenu,
+}
+
+/// The password being typed
+#[derive(Default)]
+struct Input {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Input {
+    fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.chars.clear();
+        self.cursor = 0;
+    }
+
+    /// Deletes the word before the cursor along with the whitespace after it, like a shell does
+    fn delete_word(&mut self) {
+        let end = self.cursor;
+        while self.cursor > 0 && self.chars[self.cursor - 1].is_whitespace() {
+            self.cursor -= 1;
+        }
+        while self.cursor > 0 && !self.chars[self.cursor - 1].is_whitespace() {
+            self.cursor -= 1;
+        }
+        self.chars.drain(self.cursor..end);
+    }
+}
+
+/// The password prompt, drawn with GOP when it is configured and available,
+/// or with the text console otherwise
+pub struct Greeter {
+    screen: Option<Screen>,
+    keyboard: Keyboard,
+    echo: Echo,
+    allow_reveal: bool,
+    allow_password_change: bool,
+    admin_menu: bool,
+    /// whether the password is currently shown in clear text
+    revealed: bool,
+    /// whether what's typed is not a secret, so it's revealed from the start
+    plain: bool,
+    idle_timeout: Option<u64>,
+    /// seconds until the shutdown, while waiting for a password with a timeout
+    remaining: Option<u64>,
+    /// of the new password being typed
+    strength: Option<Strength>,
+    /// the prompt, where it starts and the console width in text mode
+    prompt: String,
+    prompt_start: (usize, usize),
+    columns: usize,
+    /// the length of the currently shown line in text mode
+    shown: usize,
+}
+
+impl Greeter {
+    pub fn new(st: &mut SystemTable<Boot>, config: &Config, own_device: Handle) -> Self {
+        let screen = if config.graphical {
+            let screen = Screen::new(st, config, own_device);
+            if screen.is_none() {
+                log::info!("no graphics output, falling back to the text greeter");
+            }
+            screen
+        } else {
+            None
+        };
+        Self {
+            screen,
+            keyboard: Keyboard::new(st, &config.keyboard_layouts),
+            echo: config.echo,
+            allow_reveal: config.allow_reveal,
+            allow_password_change: config.allow_password_change,
+            admin_menu: config.admin_menu,
+            revealed: false,
+            plain: false,
+            idle_timeout: config.password_timeout,
+            remaining: None,
+            strength: None,
+            prompt: String::new(),
+            prompt_start: (0, 0),
+            columns: 80,
+            shown: 0,
+        }
+    }
+
+    /// Asks for a password, the warning is shown along with the prompt.
+    ///
+    /// With an idle timeout, the machine is shut down if no key is pressed for that long.
+    pub fn read_password(
+        &mut self,
+        st: &mut SystemTable<Boot>,
+        prompt: &str,
+        warning: Option<&str>,
+    ) -> Result<Entered> {
+        Ok(match self.read_input(st, prompt, warning, true, false)? {
+            (password, Some(ScanCode::FUNCTION_3)) => Entered::ChangePassword(password),
+            (_, Some(_)) => Entered::AdminMenu,
+            (password, None) => Entered::Password(password),
+        })
+    }
+
+    /// Asks for a password outside of the unlock prompt, e.g. the new one when
+    /// changing it, there are no hotkeys there, and optionally shows how strong
+    /// it is while it's typed
+    pub fn read_secret(
+        &mut self,
+        st: &mut SystemTable<Boot>,
+        prompt: &str,
+        warning: Option<&str>,
+        meter: bool,
+    ) -> Result<String> {
+        Ok(self.read_input(st, prompt, warning, false, meter)?.0)
+    }
+
+    /// Asks for something that is not a secret, e.g. a number, it's shown as typed
+    pub fn read_text(
+        &mut self,
+        st: &mut SystemTable<Boot>,
+        prompt: &str,
+        warning: Option<&str>,
+    ) -> Result<String> {
+        self.plain = true;
+        let text = self.read_input(st, prompt, warning, false, false);
+        self.plain = false;
+        Ok(text?.0)
+    }
+
+    /// Returns the password and the hotkey it was entered with instead of enter,
+    /// the hotkeys are only there in the `unlock` prompt
+    fn read_input(
+        &mut self,
+        st: &mut SystemTable<Boot>,
+        prompt: &str,
+        warning: Option<&str>,
+        unlock: bool,
+        meter: bool,
+    ) -> Result<(String, Option<ScanCode>)> {
+        self.remaining = self.idle_timeout;
+        self.strength = meter.then(|| Strength::of(""));
+        self.show_prompt(st, prompt, warning)?;
+
+        let mut events = Vec::with_capacity(2);
+        events.push(self.keyboard.wait_for_key_event(st));
+
+        let timer = match self.idle_timeout {
+            Some(_) => {
+                let timer = Timer::periodic(st.boot_services(), 1000).fix(info!())?;
+                events.push(unsafe { timer.event().unsafe_clone() });
+                Some(timer)
+            }
+            None => None,
+        };
+
+        let mut input = Input::default();
+        let (password, change) = loop {
+            let index = st
+                .boot_services()
+                .wait_for_event(&mut events)
+                .fix(info!())?;
+
+            if index == 1 {
+                match self.remaining {
+                    Some(secs) if secs > 1 => self.remaining = Some(secs - 1),
+                    _ => {
+                        log::info!("no password was entered in time, shutting down");
+                        st.runtime_services()
+                            .reset(ResetType::Shutdown, Status::SUCCESS, None)
+                    }
+                }
+                self.show_input(st, &input)?;
+                continue;
+            }
+
+            let press = match self.keyboard.read_key(st)? {
+                Some(press) => press,
+                None => continue,
+            };
+            let ctrl = press
+                .shift_state
+                .intersects(ShiftState::LEFT_CONTROL | ShiftState::RIGHT_CONTROL);
+
+            // any key press restarts the countdown
+            self.remaining = self.idle_timeout;
+
+            match press.key {
+                Key::Printable('\r' | '\n') => {
+                    break (input.chars.iter().collect::<String>(), None)
+                }
+                Key::Special(ScanCode::FUNCTION_3) if unlock && self.allow_password_change => {
+                    break (input.chars.iter().collect(), Some(ScanCode::FUNCTION_3))
+                }
+                Key::Special(ScanCode::FUNCTION_12) if unlock && self.admin_menu => {
+                    break (String::new(), Some(ScanCode::FUNCTION_12))
+                }
+                Key::Printable('\u{8}') => input.backspace(),
+                // the firmware either reports control characters or the letter with ctrl held
+                Key::Printable('\u{15}') => input.clear(),
+                Key::Printable('u' | 'U') if ctrl => input.clear(),
+                Key::Printable('\u{17}') => input.delete_word(),
+                Key::Printable('w' | 'W') if ctrl => input.delete_word(),
+                Key::Printable(c) if !c.is_control() && !ctrl => input.insert(c),
+                Key::Special(ScanCode::DELETE) => input.delete(),
+                Key::Special(ScanCode::LEFT) => input.cursor = input.cursor.saturating_sub(1),
+                Key::Special(ScanCode::RIGHT) => {
+                    input.cursor = (input.cursor + 1).min(input.chars.len())
+                }
+                Key::Special(ScanCode::HOME) => input.cursor = 0,
+                Key::Special(ScanCode::END) => input.cursor = input.chars.len(),
+                Key::Special(ScanCode::FUNCTION_2) if self.allow_reveal => {
+                    self.revealed = !self.revealed
+                }
+                Key::Special(ScanCode::FUNCTION_1) if self.keyboard.has_layouts() => {
+                    self.keyboard.next_layout()
+                }
+                Key::Special(ScanCode::ESCAPE) => {
+                    st.runtime_services()
+                        .reset(ResetType::Shutdown, Status::SUCCESS, None)
+                }
+                // e.g. a lock key, the indicator might need an update
+                _ => {}
+            }
+            if meter {
+                self.strength = Some(Strength::of(&input.chars.iter().collect::<String>()));
+            }
+            self.show_input(st, &input)?;
+        };
+
+        if let Some(timer) = &timer {
+            timer.cancel(st.boot_services()).fix(info!())?;
+        }
+        let strength = self.strength.take();
+        if self.remaining.take().is_some() || strength.is_some() {
+            // to not leave the countdown or the meter behind
+            self.show_input(st, &input)?;
+        }
+        self.end_input(st)?;
+        Ok((password, change))
+    }
+
+    /// Shows a message, e.g. the lockout one, under the password box
+    pub fn message(&mut self, st: &mut SystemTable<Boot>, message: &str) -> Result {
+        match &mut self.screen {
+            Some(screen) => screen.draw_message(message).fix(info!()),
+            None => {
+                st.stdout().write_str(message).unwrap();
+                Ok(())
+            }
+        }
+    }
+
+    /// Clears the screen before the next prompt
+    pub fn clear(&mut self, st: &mut SystemTable<Boot>) -> Result {
+        match &mut self.screen {
+            // the whole screen is redrawn on each prompt anyway
+            Some(_) => Ok(()),
+            None => st.stdout().clear().fix(info!()),
+        }
+    }
+
+    fn show_prompt(
+        &mut self,
+        st: &mut SystemTable<Boot>,
+        prompt: &str,
+        warning: Option<&str>,
+    ) -> Result {
+        self.shown = 0;
+        // every new prompt starts hidden again
+        self.revealed = self.plain;
+        let indicator = self.indicator();
+        match &mut self.screen {
+            Some(screen) => {
+                screen.draw_background().fix(info!())?;
+                screen
+                    .draw_box(Content::Dots(0), 0, &indicator)
+                    .fix(info!())?;
+                screen.draw_prompt(prompt).fix(info!())?;
+                match warning {
+                    Some(warning) => screen.draw_message(warning).fix(info!()),
+                    None => Ok(()),
+                }
+            }
+            None => {
+                if let Some(warning) = warning {
+                    write!(st.stdout(), "{}\r\n", warning).unwrap();
+                }
+                self.prompt = prompt.into();
+                self.prompt_start = st.stdout().cursor_position();
+                self.columns = match st.stdout().current_mode().fix(info!())? {
+                    Some(mode) => mode.columns(),
+                    None => 80,
+                };
+                self.show_input(st, &Input::default())
+            }
+        }
+    }
+
+    /// The lock keys, the active keyboard layout if there is a choice,
+    /// the strength meter and the idle countdown once it's close to running out
+    fn indicator(&self) -> String {
+        let mut flags = Vec::with_capacity(5);
+        if self.keyboard.caps_lock() {
+            flags.push(String::from("CAPS"));
+        }
+        if self.keyboard.num_lock() {
+            flags.push(String::from("NUM"));
+        }
+        if self.keyboard.has_layouts() {
+            flags.push(self.keyboard.layout().name.to_ascii_uppercase());
+        }
+        if let Some(strength) = self.strength {
+            flags.push(String::from(strength.name()));
+        }
+        match self.remaining {
+            Some(secs) if secs <= COUNTDOWN_SECS => flags.push(format!("{}s", secs)),
+            _ => {}
+        }
+        match (&self.screen, flags.is_empty()) {
+            (_, true) => String::new(),
+            (Some(_), false) => flags.join(" "),
+            (None, false) => format!("[{}] ", flags.join(" ")),
+        }
+    }
+
+    /// Where the text console cursor is after `offset` characters from the prompt start
+    fn text_position(&self, offset: usize) -> (usize, usize) {
+        let (column, row) = self.prompt_start;
+        let position = column + offset;
+        (position % self.columns, row + position / self.columns)
+    }
+
+    fn show_input(&mut self, st: &mut SystemTable<Boot>, input: &Input) -> Result {
+        let indicator = self.indicator();
+        match &mut self.screen {
+            Some(screen) => {
+                let (content, cursor) = match (self.revealed, self.echo) {
+                    (true, _) => (Content::Text(&input.chars), input.cursor),
+                    (false, Echo::Mask) => (Content::Dots(input.chars.len()), input.cursor),
+                    (false, Echo::Hidden) => (Content::Dots(0), 0),
+                };
+                screen.draw_box(content, cursor, &indicator).fix(info!())?;
+            }
+            None => {
+                // the whole line is redrawn as the indicator might have changed its length
+                let (column, row) = self.prompt_start;
+                let out = st.stdout();
+                out.set_cursor_position(column, row).fix(info!())?;
+                write!(out, "{}{}", indicator, self.prompt).unwrap();
+                let (shown, cursor) = if self.revealed || self.echo == Echo::Mask {
+                    (input.chars.len(), input.cursor)
+                } else {
+                    (0, 0)
+                };
+                for c in &input.chars[..shown] {
+                    out.write_char(if self.revealed { *c } else { '*' })
+                        .unwrap();
+                }
+                let len = indicator.chars().count() + self.prompt.chars().count();
+                let end = len + shown;
+                // erase what was shown before
+                for _ in end..self.shown {
+                    out.write_char(' ').unwrap();
+                }
+                self.shown = end;
+
+                let (column, row) = self.text_position(len + cursor);
+                st.stdout().set_cursor_position(column, row).fix(info!())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn end_input(&mut self, st: &mut SystemTable<Boot>) -> Result {
+        if self.screen.is_none() {
+            let (column, row) = self.text_position(self.shown);
+            st.stdout().set_cursor_position(column, row).fix(info!())?;
+            st.stdout().write_str("\r\n").unwrap();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{ffi::c_void, ptr};
+use uefi::{
+    prelude::BootServices,
+    proto::{device_path::DevicePath, Protocol},
+    unsafe_guid, Guid, Handle, Identify, Status,
+};
+
+use crate::boot_services_ext::BootServicesExt;
+
+/// The vendor GUID of the media device path that the Linux efistub
+/// (since 5.8) looks up a LoadFile2 protocol on to get the initrd from
+#[unsafe_guid("5568e427-68fc-4f3d-ac74-ca555231cc68")]
+struct LinuxInitrdMedia;
+
+#[repr(C, packed)]
+struct VendorMediaNode {
+    device_type: u8,
+    sub_type: u8,
+    length: [u8; 2],
+    guid: Guid,
+}
+
+#[repr(C, packed)]
+struct InitrdDevicePath {
+    vendor: VendorMediaNode,
+    end: [u8; 4],
+}
+
+#[unsafe_guid("4006c0c1-fcb3-403e-996d-4a6c8724e06d")]
+#[derive(Protocol)]
+#[repr(C)]
+struct InitrdLoadFile2 {
+    load_file: unsafe extern "efiapi" fn(
+        this: &InitrdLoadFile2,
+        file_path: *const DevicePath,
+        boot_policy: bool,
+        buffer_size: &mut usize,
+        buffer: *mut u8,
+    ) -> Status,
+    // not a part of the protocol, the firmware only sees the fn pointer above
+    data: Vec<u8>,
+}
+
+unsafe extern "efiapi" fn load_file(
+    this: &InitrdLoadFile2,
+    _file_path: *const DevicePath,
+    boot_policy: bool,
+    buffer_size: &mut usize,
+    buffer: *mut u8,
+) -> Status {
+    // LoadFile2 is not allowed to be used as a boot option
+    if boot_policy {
+        return Status::UNSUPPORTED;
+    }
+    let len = this.data.len();
+    if buffer.is_null() || *buffer_size < len {
+        *buffer_size = len;
+        return Status::BUFFER_TOO_SMALL;
+    }
+    ptr::copy_nonoverlapping(this.data.as_ptr(), buffer, len);
+    *buffer_size = len;
+    Status::SUCCESS
+}
+
+/// Installs the LoadFile2 protocol serving the given initrd on the
+/// `LINUX_EFI_INITRD_MEDIA_GUID` vendor media device path.
+///
+/// Both the device path and the data are leaked, as they have to stay
+/// alive until the kernel decides to load the initrd.
+pub fn install(bt: &BootServices, data: Vec<u8>) -> uefi::Result<Handle> {
+    let device_path = Box::leak(Box::new(InitrdDevicePath {
+        vendor: VendorMediaNode {
+            device_type: 0x04, // media
+            sub_type: 0x03,    // vendor
+            length: (core::mem::size_of::<VendorMediaNode>() as u16).to_le_bytes(),
+            guid: LinuxInitrdMedia::GUID,
+        },
+        end: [0x7F, 0xFF, 0x04, 0x00],
+    }));
+    let load_file2 = Box::leak(Box::new(InitrdLoadFile2 { load_file, data }));
+
+    unsafe {
+        let handle = bt
+            .install_protocol_interface(
+                None,
+                &DevicePath::GUID,
+                device_path as *mut _ as *mut c_void,
+            )?
+            .log();
+        bt.install_protocol_interface(
+            Some(handle),
+            &InitrdLoadFile2::GUID,
+            load_file2 as *mut _ as *mut c_void,
+        )?
+        .log();
+        Ok(handle.into())
+    }
+}
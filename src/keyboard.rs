@@ -0,0 +1,230 @@
+use alloc::vec::Vec;
+use core::fmt;
+use uefi::{
+    prelude::*,
+    proto::console::text::{self, ScanCode},
+    Event,
+};
+
+use crate::{
+    error::{Result, ResultFixupExt},
+    info,
+    text_input_ex::{console_in_handle, ShiftState, SimpleTextInputEx, ToggleState},
+};
+
+/// A keyboard layout as a remapping of the characters the firmware reports
+/// for a US keyboard to the ones on the same keys of the layout
+pub struct Layout {
+    pub name: &'static str,
+    map: &'static [(char, char)],
+    /// with right Alt (AltGr) held, looked up by the unshifted US character
+    alt_gr: &'static [(char, char)],
+}
+
+impl Layout {
+    pub fn find(name: &str) -> Option<&'static Layout> {
+        LAYOUTS.iter().find(|l| l.name.eq_ignore_ascii_case(name))
+    }
+
+    fn remap(&self, c: char, alt_gr: bool) -> char {
+        let table = if alt_gr { self.alt_gr } else { self.map };
+        match table.iter().find(|(us, _)| *us == c) {
+            Some((_, mapped)) => *mapped,
+            // AltGr with nothing on the key types what the key has
+            None if alt_gr => self.remap(c, false),
+            None => c,
+        }
+    }
+}
+
+impl fmt::Debug for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+#[rustfmt::skip]
+pub const LAYOUTS: &[Layout] = &[
+    Layout {
+        name: "us",
+        map: &[],
+        alt_gr: &[],
+    },
+    Layout {
+        name: "de",
+        map: &[
+            ('y', 'z'), ('z', 'y'), ('Y', 'Z'), ('Z', 'Y'),
+            ('`', '^'), ('~', '°'),
+            ('@', '"'), ('#', '§'), ('^', '&'), ('&', '/'),
+            ('*', '('), ('(', ')'), (')', '='),
+            ('-', 'ß'), ('_', '?'), ('=', '´'), ('+', '`'),
+            ('[', 'ü'), ('{', 'Ü'), (']', '+'), ('}', '*'),
+            (';', 'ö'), (':', 'Ö'), ('\'', 'ä'), ('"', 'Ä'),
+            ('\\', '#'), ('|', '\''),
+            ('<', ';'), ('>', ':'), ('/', '-'), ('?', '_'),
+        ],
+        alt_gr: &[
+            ('q', '@'), ('e', '€'), ('m', 'µ'),
+            ('2', '²'), ('3', '³'), ('7', '{'), ('8', '['), ('9', ']'), ('0', '}'),
+            ('-', '\\'), (']', '~'),
+        ],
+    },
+    Layout {
+        name: "fr",
+        map: &[
+            ('q', 'a'), ('w', 'z'), ('a', 'q'), ('z', 'w'), ('m', ','), (';', 'm'),
+            ('Q', 'A'), ('W', 'Z'), ('A', 'Q'), ('Z', 'W'), ('M', '?'), (':', 'M'),
+            ('`', '²'),
+            ('1', '&'), ('2', 'é'), ('3', '"'), ('4', '\''), ('5', '('),
+            ('6', '-'), ('7', 'è'), ('8', '_'), ('9', 'ç'), ('0', 'à'), ('-', ')'),
+            ('!', '1'), ('@', '2'), ('#', '3'), ('$', '4'), ('%', '5'),
+            ('^', '6'), ('&', '7'), ('*', '8'), ('(', '9'), (')', '0'), ('_', '°'),
+            ('[', '^'), ('{', '¨'), (']', '$'), ('}', '£'),
+            ('\'', 'ù'), ('"', '%'), ('\\', '*'), ('|', 'µ'),
+            (',', ';'), ('<', '.'), ('.', ':'), ('>', '/'), ('/', '!'), ('?', '§'),
+        ],
+        alt_gr: &[
+            ('2', '~'), ('3', '#'), ('4', '{'), ('5', '['), ('6', '|'),
+            ('7', '`'), ('8', '\\'), ('9', '^'), ('0', '@'), ('-', ']'), ('=', '}'),
+            ('e', '€'),
+        ],
+    },
+];
+
+pub enum Key {
+    Printable(char),
+    Special(ScanCode),
+}
+
+pub struct KeyPress {
+    pub key: Key,
+    pub shift_state: ShiftState,
+    pub toggle_state: ToggleState,
+}
+
+/// Reads the keys through the extended text input protocol when
+/// the console has it, to know about the modifiers, and remaps them
+/// according to the selected keyboard layout
+pub struct Keyboard {
+    ex: Option<*mut SimpleTextInputEx>,
+    layouts: Vec<&'static Layout>,
+    current: usize,
+    /// as of the last key press, if the firmware reports it
+    toggle_state: ToggleState,
+}
+
+impl Keyboard {
+    pub fn new(st: &SystemTable<Boot>, layouts: &[&'static Layout]) -> Self {
+        let ex = st
+            .boot_services()
+            .handle_protocol::<SimpleTextInputEx>(console_in_handle(st))
+            .log_warning()
+            .ok()
+            .map(|ex| ex.get());
+        if ex.is_none() {
+            log::debug!("no extended text input, keyboard layouts only work without AltGr");
+        }
+        let layouts = if layouts.is_empty() {
+            vec![&LAYOUTS[0]]
+        } else {
+            layouts.to_vec()
+        };
+        Self {
+            ex,
+            layouts,
+            current: 0,
+            toggle_state: ToggleState::empty(),
+        }
+    }
+
+    pub fn layout(&self) -> &'static Layout {
+        self.layouts[self.current]
+    }
+
+    /// Whether there is anything to switch between
+    pub fn has_layouts(&self) -> bool {
+        self.layouts.len() > 1
+    }
+
+    pub fn caps_lock(&self) -> bool {
+        self.toggle_state.contains(ToggleState::CAPS_LOCK)
+    }
+
+    pub fn num_lock(&self) -> bool {
+        self.toggle_state.contains(ToggleState::NUM_LOCK)
+    }
+
+    pub fn next_layout(&mut self) {
+        self.current = (self.current + 1) % self.layouts.len();
+    }
+
+    pub fn wait_for_key_event(&self, st: &SystemTable<Boot>) -> Event {
+        match self.ex {
+            Some(ex) => unsafe { (*ex).wait_for_key_event().unsafe_clone() },
+            None => unsafe { st.stdin().wait_for_key_event().unsafe_clone() },
+        }
+    }
+
+    /// Once the lock states are known, asks the firmware to also report
+    /// the presses of the keys without a character, so that the lock key
+    /// presses are seen right away. It cannot be done before as setting
+    /// the state sets the locks too.
+    fn update_toggle_state(&mut self, ex: *mut SimpleTextInputEx, state: ToggleState) {
+        if !self.toggle_state.contains(ToggleState::KEY_STATE_EXPOSED)
+            && !state.contains(ToggleState::KEY_STATE_EXPOSED)
+        {
+            let exposed = state | ToggleState::KEY_STATE_EXPOSED;
+            // not every firmware supports it, the state is still updated on other key presses
+            if unsafe { &mut *ex }.set_state(exposed).log_warning().is_ok() {
+                self.toggle_state = exposed;
+                return;
+            }
+        }
+        self.toggle_state = state;
+    }
+
+    pub fn read_key(&mut self, st: &SystemTable<Boot>) -> Result<Option<KeyPress>> {
+        let (key, shift_state, toggle_state) = match self.ex {
+            Some(ex) => {
+                let data = match unsafe { &mut *ex }.read_key_stroke().fix(info!())? {
+                    Some(data) => data,
+                    None => return Ok(None),
+                };
+                let key = match data.scan_code() {
+                    ScanCode::NULL => match char::from_u32(data.unicode_char as u32) {
+                        Some(c) => Key::Printable(c),
+                        None => return Ok(None),
+                    },
+                    scan_code => Key::Special(scan_code),
+                };
+                let toggle_state = data.toggle_state();
+                if toggle_state.contains(ToggleState::VALID) {
+                    self.update_toggle_state(ex, toggle_state);
+                }
+                (key, data.shift_state(), toggle_state)
+            }
+            None => {
+                let key = match st.stdin().read_key().fix(info!())? {
+                    Some(text::Key::Printable(c)) => Key::Printable(c.into()),
+                    Some(text::Key::Special(scan_code)) => Key::Special(scan_code),
+                    None => return Ok(None),
+                };
+                (key, ShiftState::empty(), ToggleState::empty())
+            }
+        };
+
+        let key = match key {
+            // control characters are the same in all layouts
+            Key::Printable(c) if !c.is_control() => {
+                let alt_gr = shift_state.contains(ShiftState::RIGHT_ALT);
+                Key::Printable(self.layout().remap(c, alt_gr))
+            }
+            key => key,
+        };
+        Ok(Some(KeyPress {
+            key,
+            shift_state,
+            toggle_state,
+        }))
+    }
+}
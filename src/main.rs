@@ -14,45 +14,64 @@ extern crate alloc;
 extern crate rlibc;
 
 use alloc::{string::String, vec::Vec};
-use core::{convert::TryFrom, fmt::Write, time::Duration};
+use core::{convert::TryFrom, time::Duration};
+use sha2::{Digest, Sha256};
 
 use uefi::{
     prelude::*,
     proto::{
-        console::text::{Key, ScanCode},
         device_path::DevicePath,
         loaded_image::LoadedImage,
         media::{
             block::BlockIO,
             file::{File, FileAttribute, FileInfo, FileMode, FileType},
             fs::SimpleFileSystem,
-            partition::{GptPartitionType, PartitionInfo},
         },
     },
     table::{boot::MemoryType, runtime::ResetType},
-    CStr16, CString16,
+    CString16,
 };
 
 use crate::{
+    boot_manager::boot_from_boot_order,
     boot_services_ext::BootServicesExt,
-    config::Config,
+    config::{BootEntry, BootTarget, Config, ImageTarget},
+    discover::discover,
     error::{Error, OpalError, Result, ResultFixupExt},
+    greeter::{Entered, Greeter},
     nvme_device::NvmeDevice,
     nvme_passthru::*,
-    opal::{session::OpalSession, uid, LockingState, StatusCode},
-    secure_device::SecureDevice,
+    opal::{
+        session::{self, OpalSession},
+        uid, Authority, LockingState, StatusCode,
+    },
+    partition::find_boot_partition,
+    policy::Policy,
+    secure_device::{LockingFlags, SecureDevice},
     util::sleep,
 };
 
+pub mod admin;
+pub mod boot_manager;
 pub mod boot_services_ext;
 pub mod config;
+pub mod discover;
 pub mod dp_to_text;
 pub mod error;
+pub mod gfx;
+pub mod greeter;
+pub mod initrd;
+pub mod keyboard;
+pub mod menu;
 pub mod nvme_device;
 pub mod nvme_passthru;
 pub mod opal;
+pub mod partition;
+pub mod policy;
 pub mod secure_device;
+pub mod text_input_ex;
 pub mod util;
+pub mod vars;
 
 #[entry]
 fn main(image_handle: Handle, mut st: SystemTable<Boot>) -> Status {
@@ -73,55 +92,259 @@ fn main(image_handle: Handle, mut st: SystemTable<Boot>) -> Status {
 fn run(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result {
     config_stdout(st).fix(info!())?;
 
-    let config = load_config(image_handle, st)?;
+    let mut config = load_config(image_handle, st)?;
+    let own_device = own_device(image_handle, st)?;
+
+    if let Some(path) = config.password_denylist.clone() {
+        match read_file(st, own_device, &path).log_warning() {
+            Ok(Some(data)) => config
+                .password_policy
+                .extend_denylist(&String::from_utf8_lossy(&data)),
+            _ => log::warn!("password denylist {} not found", path),
+        }
+    }
 
-    let devices = find_secure_devices(st).fix(info!())?;
+    let mut devices = find_secure_devices(st).fix(info!())?;
+    let mut greeter = Greeter::new(st, &config, own_device);
+
+    // to know which partitions popped up after unlocking
+    let known_partitions = st
+        .boot_services()
+        .find_handles::<SimpleFileSystem>()
+        .fix(info!())?;
 
-    for mut device in devices {
-        if device.recv_locked().fix(info!())? {
+    // bad passwords for all of the drives
+    let mut failures = 0;
+
+    // the passwords the drives were unlocked with, to change them together
+    let mut passwords = vec![None; devices.len()];
+
+    // nothing to unlock means there is no prompt to get to the admin menu from
+    let mut prompted = false;
+
+    let mut i = 0;
+    while i < devices.len() {
+        if devices[i].recv_locked().fix(info!())? {
+            prompted = true;
+            let mut changed = None;
             // session mutably borrows the device
             {
-                let mut prompt = config.prompt.as_deref().unwrap_or("password: ");
-                let mut session = loop {
-                    let password = read_password(st, prompt)?;
-
-                    let mut hash = vec![0; 32];
-
-                    // as in sedutil-cli, maybe will change
-                    pbkdf2::pbkdf2::<hmac::Hmac<sha1::Sha1>>(
-                        password.as_bytes(),
-                        device.proto().serial_num(),
-                        75000,
-                        &mut hash,
-                    );
-
-                    if let Some(s) =
-                        pretty_session(st, &mut device, &*hash, config.sed_locked_msg.as_deref())?
-                    {
-                        break s;
+                let device = &mut devices[i];
+                let serial = device.proto().serial_num().to_vec();
+                let authority = unlock_authority(device, &config)?;
+                let mut prompt = String::from(config.prompt.as_deref().unwrap_or("password: "));
+                let mut warning = None;
+                let session = loop {
+                    let (password, change) = match greeter.read_password(st, &prompt, warning)? {
+                        Entered::Password(password) => (password, false),
+                        Entered::ChangePassword(password) => (password, true),
+                        Entered::AdminMenu => break None,
+                    };
+
+                    if let Some(mut s) = pretty_session(
+                        st,
+                        &mut greeter,
+                        device,
+                        authority,
+                        &hash_password(&password, &serial),
+                        config.sed_locked_msg.as_deref(),
+                    )? {
+                        if change {
+                            let new = read_new_password(st, &mut greeter, &config.password_policy)?;
+                            s.set_pin(authority.c_pin_uid(), &hash_password(&new, &serial))?;
+                            changed = Some(password);
+                            passwords[i] = Some(new);
+                        } else {
+                            passwords[i] = Some(password);
+                        }
+                        break Some(s);
+                    }
+
+                    failures += 1;
+                    if matches!(config.max_retries, Some(max) if failures >= max) {
+                        greeter.message(
+                            st,
+                            config
+                                .max_retries_msg
+                                .as_deref()
+                                .unwrap_or("Too many bad passwords, shutting down in 10s.."),
+                        )?;
+                        sleep(Duration::from_secs(10));
+                        st.runtime_services()
+                            .reset(ResetType::Shutdown, Status::SUCCESS, None);
                     }
 
                     if config.clear_on_retry {
-                        st.stdout().clear().fix(info!())?;
+                        greeter.clear(st)?;
                     }
 
-                    prompt = config
+                    let retry_prompt = config
                         .retry_prompt
                         .as_deref()
                         .unwrap_or("bad password, retry: ");
+                    let retries_left = config.max_retries.map(|max| (max - failures) as u64);
+                    let drive_left = remaining_tries(device, authority);
+                    let left = match (drive_left, retries_left) {
+                        (Some(left), Some(retries_left)) => Some(left.min(retries_left)),
+                        (left, retries_left) => left.or(retries_left),
+                    };
+                    prompt = match left {
+                        Some(left) => format!("({} attempts left) {}", left, retry_prompt),
+                        None => retry_prompt.into(),
+                    };
+                    warning =
+                        if drive_left == Some(1) {
+                            Some(config.last_attempt_msg.as_deref().unwrap_or(
+                                "LAST ATTEMPT - another bad password locks the SED out!",
+                            ))
+                        } else if left == Some(1) {
+                            // it's our own max-retries running out, the drive would still take more
+                            Some("LAST ATTEMPT - another bad password shuts down the machine!")
+                        } else {
+                            None
+                        };
                 };
 
-                session.set_mbr_done(true)?;
-                session.set_locking_range(0, LockingState::ReadWrite)?;
+                let mut session = match session {
+                    Some(session) => session,
+                    None => {
+                        // for the borrow of the device to end
+                        drop(session);
+                        admin::run(st, &mut greeter, &mut devices, &config, own_device)?;
+                        // back to the same drive, it might be unlocked by now
+                        continue;
+                    }
+                };
+                if let Err(e) = session.set_mbr_done(true) {
+                    // it's up to the admin whether the users can do that
+                    if matches!(authority, Authority::Admin(_)) {
+                        return Err(e);
+                    }
+                    log::warn!("{} could not set the shadow MBR done: {:?}", authority, e);
+                }
+                session.set_locking_range(config.locking_range, LockingState::ReadWrite)?;
             }
 
             // reconnect the controller to see
             // the real partition pop up after unlocking
-            device.reconnect_controller(st).fix(info!())?;
+            devices[i].reconnect_controller(st).fix(info!())?;
+
+            if let Some(old) = changed {
+                let new = passwords[i].clone().unwrap_or_default();
+                // only the drives already unlocked with the old password, trying it
+                // on the others could use up the tries of their own passwords
+                for (device, password) in devices.iter_mut().zip(&mut passwords) {
+                    if password.as_deref() != Some(&*old) {
+                        continue;
+                    }
+                    let authority = unlock_authority(device, &config)?;
+                    match change_password(device, authority, &old, &new) {
+                        Ok(()) => *password = Some(new.clone()),
+                        Err(e) => log::warn!("did not change the password of a drive: {:?}", e),
+                    }
+                }
+            }
         }
+        i += 1;
     }
 
-    let handle = find_boot_partition(st)?;
+    if let Some(expected) = config.mbr_sha256 {
+        for device in &mut devices {
+            verify_mbr(st, device, &expected, config.mbr_sha256_size)?;
+        }
+    }
+
+    if config.admin_menu && !prompted {
+        admin::run(st, &mut greeter, &mut devices, &config, own_device)?;
+    }
+
+    for device in &mut devices {
+        let serial: String = String::from_utf8_lossy(device.proto().serial_num())
+            .trim()
+            .into();
+        if config.blocks_sid(&serial) {
+            // one drive failing to do it is no reason to not boot
+            if let Err(e) = block_sid(device, &serial, config.block_sid_hardware_reset) {
+                log::warn!("could not block the SID of drive {}: {:?}", serial, e);
+            }
+        }
+    }
+
+    if config.entries.is_empty() {
+        config.entries = discover(st, own_device, &known_partitions)?;
+    }
+
+    let entry = select_entry(st, &config)?;
+    match &entry.target {
+        BootTarget::Image(target) => boot_image(image_handle, st, own_device, target, &mut devices),
+        BootTarget::BootOrder => boot_from_boot_order(image_handle, st, own_device),
+    }
+}
+
+const LAST_ENTRY_VAR: &str = "LastEntry";
+
+fn select_entry<'c>(st: &mut SystemTable<Boot>, config: &'c Config) -> Result<&'c BootEntry> {
+    let entries = &config.entries;
+    if entries.len() == 1 {
+        return Ok(&entries[0]);
+    }
+
+    let last = vars::get(
+        st.runtime_services(),
+        LAST_ENTRY_VAR,
+        &vars::greeter_vendor(),
+    )
+    .fix(info!())
+    .ok()
+    .flatten();
+
+    let position = |name: &[u8]| entries.iter().position(|e| e.name.as_bytes() == name);
+    let default = last
+        .as_deref()
+        .and_then(position)
+        .or_else(|| {
+            config
+                .default_entry
+                .as_deref()
+                .and_then(|d| position(d.as_bytes()))
+        })
+        .unwrap_or_default();
+
+    let selected = if config.timeout == Some(0) {
+        default
+    } else {
+        let names = entries.iter().map(|e| &*e.name).collect::<Vec<_>>();
+        menu::choose(
+            st,
+            "select the boot entry:",
+            &names,
+            default,
+            config.timeout,
+        )?
+    };
+
+    let entry = &entries[selected];
+    if last.as_deref() != Some(entry.name.as_bytes()) {
+        // not critical if it fails, just log it
+        let _ = vars::set(
+            st.runtime_services(),
+            LAST_ENTRY_VAR,
+            &vars::greeter_vendor(),
+            entry.name.as_bytes(),
+        )
+        .fix(info!());
+    }
+    Ok(entry)
+}
+
+fn boot_image(
+    image_handle: Handle,
+    st: &mut SystemTable<Boot>,
+    own_device: Handle,
+    entry: &ImageTarget,
+    devices: &mut [SecureDevice],
+) -> Result {
+    let handle = find_boot_partition(st, own_device, entry.partition.as_ref(), devices)?;
 
     let dp = st
         .boot_services()
@@ -129,15 +352,26 @@ fn run(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result {
         .fix(info!())?;
     let dp = unsafe { &mut *dp.get() };
 
-    let image = config.image;
-    let buf = read_file(st, handle, &image)
+    let buf = read_file(st, handle, &entry.image)
         .fix(info!())?
-        .ok_or(Error::ImageNotFound(image))?;
+        .ok_or_else(|| Error::ImageNotFound(entry.image.clone()))?;
 
     if buf.get(0..2) != Some(&[0x4d, 0x5a]) {
         return Err(Error::ImageNotPeCoff);
     }
 
+    if !entry.initrd.is_empty() {
+        let mut initrd = Vec::new();
+        // multiple initrds are just concatenated, e.g. microcode + the main one
+        for path in &entry.initrd {
+            let data = read_file(st, handle, path)
+                .fix(info!())?
+                .ok_or_else(|| Error::InitrdNotFound(path.clone()))?;
+            initrd.extend(data);
+        }
+        initrd::install(st.boot_services(), initrd).fix(info!())?;
+    }
+
     let loaded_image_handle = st
         .boot_services()
         .load_image(false, image_handle, Some(dp), Some(&buf))
@@ -148,7 +382,7 @@ fn run(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result {
         .fix(info!())?;
     let loaded_image = unsafe { &mut *loaded_image.get() };
 
-    let args = CString16::try_from(&*config.args).or(Err(Error::ConfigArgsBadUtf16))?;
+    let args = CString16::try_from(&*entry.args).or(Err(Error::ConfigArgsBadUtf16))?;
     unsafe { loaded_image.set_load_options(args.as_ptr(), args.num_bytes() as _) };
 
     st.boot_services()
@@ -170,14 +404,20 @@ fn config_stdout(st: &mut SystemTable<Boot>) -> uefi::Result {
     Ok(().into())
 }
 
-fn load_config(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result<Config> {
+/// The handle of the partition the greeter was loaded from
+fn own_device(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result<Handle> {
     let loaded_image = st
         .boot_services()
         .handle_protocol::<LoadedImage>(image_handle)
         .fix(info!())?;
+    Ok(unsafe { &*loaded_image.get() }.device())
+}
+
+fn load_config(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result<Config> {
+    let device = own_device(image_handle, st)?;
     let device_path = st
         .boot_services()
-        .handle_protocol::<DevicePath>(unsafe { &*loaded_image.get() }.device())
+        .handle_protocol::<DevicePath>(device)
         .fix(info!())?;
     let device_handle = st
         .boot_services()
@@ -192,69 +432,132 @@ fn load_config(image_handle: Handle, st: &mut SystemTable<Boot>) -> Result<Confi
     Ok(config)
 }
 
-fn write_char(st: &mut SystemTable<Boot>, ch: u16) -> Result {
-    let str = &[ch, 0];
-    st.stdout()
-        .output_string(unsafe { CStr16::from_u16_with_nul_unchecked(str) })
-        .fix(info!())
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
-fn read_password(st: &mut SystemTable<Boot>, prompt: &str) -> Result<String> {
-    st.stdout().write_str(prompt).unwrap();
+/// The SHA-256 of the first `size` bytes of the shadow MBR, or of all of it
+fn mbr_sha256(device: &mut SecureDevice, size: Option<u64>) -> Result<[u8; 32]> {
+    let properties = session::properties(device).unwrap_or_default();
+    // the shadow MBR is readable by anybody
+    let mut session = OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ANYBODY, None)?
+        .buffer_size(properties.max_com_packet_size);
+    // never past the end of the table, whatever the config says
+    let table_size = session.mbr_size()?;
+    let size = size.map_or(table_size, |size| size.min(table_size));
+    let mut hasher = Sha256::new();
+    session.read_mbr_chunks(size, properties.chunk_size(), |chunk| hasher.update(chunk))?;
+    Ok(hasher.finalize().into())
+}
 
-    let mut wait_for_key = [unsafe { st.stdin().wait_for_key_event().unsafe_clone() }];
+/// Checks the shadow MBR of a drive that has it enabled against the expected hash,
+/// asking whether to boot anyway if it doesn't match
+fn verify_mbr(
+    st: &mut SystemTable<Boot>,
+    device: &mut SecureDevice,
+    expected: &[u8; 32],
+    size: Option<u64>,
+) -> Result {
+    let locking = device.recv_locking().fix(info!())?;
+    let mbr_enabled = matches!(locking, Some(flags) if flags.contains(LockingFlags::MBR_ENABLED));
+    if !mbr_enabled {
+        return Ok(());
+    }
+    let title = match mbr_sha256(device, size) {
+        Ok(hash) if &hash == expected => return Ok(()),
+        Ok(hash) => {
+            log::warn!(
+                "shadow MBR does not match mbr-sha256, its SHA-256 is {}",
+                hex(&hash)
+            );
+            format!(
+                "the shadow MBR of the drive does not match the expected one,\nits SHA-256 is {}\nexpected {}",
+                hex(&hash),
+                hex(expected)
+            )
+        }
+        Err(e) => {
+            log::warn!("could not read the shadow MBR to check it: {:?}", e);
+            format!(
+                "the shadow MBR of the drive could not be read to check it:\n{:?}",
+                e
+            )
+        }
+    };
+    if menu::choose(st, &title, &["shut down", "boot anyway"], 0, None)? == 0 {
+        st.runtime_services()
+            .reset(ResetType::Shutdown, Status::SUCCESS, None);
+    }
+    Ok(())
+}
+
+fn hash_password(password: &str, serial: &[u8]) -> Vec<u8> {
+    let mut hash = vec![0; 32];
+    // as in sedutil-cli, maybe will change
+    pbkdf2::pbkdf2::<hmac::Hmac<sha1::Sha1>>(password.as_bytes(), serial, 75000, &mut hash);
+    hash
+}
 
-    let mut data = String::with_capacity(32);
+/// Asks for the new password twice, until both match and it satisfies the policy
+fn read_new_password(
+    st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
+    policy: &Policy,
+) -> Result<String> {
+    let mut warning = None;
     loop {
-        st.boot_services()
-            .wait_for_event(&mut wait_for_key)
-            .fix(info!())?;
-
-        match st.stdin().read_key().fix(info!())? {
-            Some(Key::Printable(k)) if [0xD, 0xA].contains(&u16::from(k)) => {
-                write_char(st, 0x0D)?;
-                write_char(st, 0x0A)?;
-                break Ok(data);
-            }
-            Some(Key::Printable(k)) if u16::from(k) == 0x8 => {
-                if data.pop().is_some() {
-                    write_char(st, 0x08)?;
-                }
-            }
-            Some(Key::Printable(k)) => {
-                write_char(st, '*' as u16)?;
-                data.push(k.into());
-            }
-            Some(Key::Special(ScanCode::ESCAPE)) => {
-                st.runtime_services()
-                    .reset(ResetType::Shutdown, Status::SUCCESS, None)
-            }
-            _ => {}
+        let new = greeter.read_secret(st, "new password: ", warning.as_deref(), true)?;
+        warning = policy.check(&new);
+        if warning.is_some() {
+            continue;
+        }
+        if greeter.read_secret(st, "repeat the new password: ", None, false)? == new {
+            break Ok(new);
         }
+        warning = Some("the passwords do not match".into());
     }
 }
 
+/// Sets the new password on a drive that accepts the old one
+fn change_password(
+    device: &mut SecureDevice,
+    authority: Authority,
+    old: &str,
+    new: &str,
+) -> Result {
+    let serial = device.proto().serial_num().to_vec();
+    let mut session = OpalSession::start(
+        device,
+        uid::OPAL_LOCKINGSP,
+        authority.uid(),
+        Some(&hash_password(old, &serial)),
+    )?;
+    session.set_pin(authority.c_pin_uid(), &hash_password(new, &serial))?;
+    log::info!("changed the password of a drive");
+    Ok(())
+}
+
 fn pretty_session<'d>(
     st: &mut SystemTable<Boot>,
+    greeter: &mut Greeter,
     device: &'d mut SecureDevice,
+    authority: Authority,
     challenge: &[u8],
     sed_locked_msg: Option<&str>,
 ) -> Result<Option<OpalSession<'d>>> {
     match OpalSession::start(
         device,
         uid::OPAL_LOCKINGSP,
-        uid::OPAL_ADMIN1,
+        authority.uid(),
         Some(challenge),
     ) {
         Ok(session) => Ok(Some(session)),
         Err(Error::Opal(OpalError::Status(StatusCode::NOT_AUTHORIZED))) => Ok(None),
         Err(Error::Opal(OpalError::Status(StatusCode::AUTHORITY_LOCKED_OUT))) => {
-            st.stdout()
-                .write_str(
-                    sed_locked_msg
-                        .unwrap_or("Too many bad tries, SED locked out, resetting in 10s.."),
-                )
-                .unwrap();
+            greeter.message(
+                st,
+                sed_locked_msg.unwrap_or("Too many bad tries, SED locked out, resetting in 10s.."),
+            )?;
             sleep(Duration::from_secs(10));
             st.runtime_services()
                 .reset(ResetType::Cold, Status::WARN_RESET_REQUIRED, None);
@@ -263,6 +566,72 @@ fn pretty_session<'d>(
     }
 }
 
+/// Blocks the SID authentication, reporting drives that can't do it
+fn block_sid(device: &mut SecureDevice, serial: &str, hardware_reset: bool) -> Result {
+    match device.recv_block_sid().fix(info!())? {
+        None => log::warn!(
+            "drive {} does not support Block SID, not blocking it",
+            serial
+        ),
+        Some(state) if state.blocked => log::info!("SID of drive {} is already blocked", serial),
+        Some(state) => {
+            if state.sid_is_msid {
+                log::warn!(
+                    "drive {} is not owned, its SID password is the MSID",
+                    serial
+                );
+            }
+            device.block_sid(hardware_reset).fix(info!())?;
+            match device.recv_block_sid().fix(info!())? {
+                Some(state) if state.blocked => log::info!("blocked SID of drive {}", serial),
+                _ => log::warn!("drive {} did not block the SID", serial),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How many tries the password has left, read by anybody as it doesn't
+/// need a password, any failure just means there is nothing to show
+fn remaining_tries(device: &mut SecureDevice, authority: Authority) -> Option<u64> {
+    let result = OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ANYBODY, None)
+        .and_then(|mut session| session.remaining_tries(authority.c_pin_uid()));
+    match result {
+        Ok(left) => left,
+        Err(e) => {
+            log::debug!("could not read the remaining tries: {:?}", e);
+            None
+        }
+    }
+}
+
+/// The authority from the config, or the owner of the range if the drive is in the
+/// Single User Mode, as only they can unlock it then, or Admin1 otherwise
+fn unlock_authority(device: &mut SecureDevice, config: &Config) -> Result<Authority> {
+    if let Some(authority) = config.authority {
+        return Ok(authority);
+    }
+    let single_user = match device.recv_single_user().fix(info!())? {
+        Some(sum) if sum.all => true,
+        // only some of the ranges are, maybe not ours
+        Some(sum) if sum.any => {
+            OpalSession::start(device, uid::OPAL_LOCKINGSP, uid::OPAL_ANYBODY, None)
+                .and_then(|mut session| session.is_single_user(config.locking_range))
+                .unwrap_or_else(|e| {
+                    log::warn!("could not read the Single User Mode ranges: {:?}", e);
+                    false
+                })
+        }
+        _ => false,
+    };
+    Ok(if single_user {
+        // User1 owns the global range, User2 owns range 1 and so on
+        Authority::User(config.locking_range.saturating_add(1))
+    } else {
+        Authority::default()
+    })
+}
+
 fn find_secure_devices(st: &mut SystemTable<Boot>) -> uefi::Result<Vec<SecureDevice>> {
     let mut result = Vec::new();
 
@@ -315,31 +684,6 @@ fn find_secure_devices(st: &mut SystemTable<Boot>) -> uefi::Result<Vec<SecureDev
     Ok(result.into())
 }
 
-fn find_boot_partition(st: &mut SystemTable<Boot>) -> Result<Handle> {
-    let mut res = None;
-    for handle in st
-        .boot_services()
-        .find_handles::<PartitionInfo>()
-        .fix(info!())?
-    {
-        let pi = st
-            .boot_services()
-            .handle_protocol::<PartitionInfo>(handle)
-            .fix(info!())?;
-        let pi = unsafe { &mut *pi.get() };
-
-        match pi.gpt_partition_entry() {
-            Some(gpt) if { gpt.partition_type_guid } == GptPartitionType::EFI_SYSTEM_PARTITION => {
-                if res.replace(handle).is_some() {
-                    return Err(Error::MultipleBootPartitions);
-                }
-            }
-            _ => {}
-        }
-    }
-    res.ok_or(Error::NoBootPartitions)
-}
-
 fn read_file(
     st: &mut SystemTable<Boot>,
     device: Handle,
@@ -0,0 +1,115 @@
+use alloc::vec::Vec;
+use core::fmt::Write;
+use uefi::{
+    prelude::*,
+    proto::console::text::{Color, Key, ScanCode},
+    table::runtime::ResetType,
+};
+
+use crate::{
+    error::{Result, ResultFixupExt},
+    info,
+    util::Timer,
+};
+
+/// Shows a simple text menu and returns the index of the chosen item.
+///
+/// With a timeout, the `default` item is chosen once the countdown runs out,
+/// any key press stops the countdown.
+pub fn choose(
+    st: &mut SystemTable<Boot>,
+    title: &str,
+    items: &[&str],
+    default: usize,
+    timeout: Option<u64>,
+) -> Result<usize> {
+    let mut selected = default.min(items.len().saturating_sub(1));
+    let mut remaining = timeout;
+
+    let mut events = Vec::with_capacity(2);
+    events.push(unsafe { st.stdin().wait_for_key_event().unsafe_clone() });
+
+    let timer = match timeout {
+        Some(_) => {
+            let timer = Timer::periodic(st.boot_services(), 1000).fix(info!())?;
+            events.push(unsafe { timer.event().unsafe_clone() });
+            Some(timer)
+        }
+        None => None,
+    };
+
+    loop {
+        draw(st, title, items, selected, remaining)?;
+
+        let index = st
+            .boot_services()
+            .wait_for_event(&mut events)
+            .fix(info!())?;
+
+        if index == 1 {
+            match remaining {
+                Some(secs) if secs > 1 => remaining = Some(secs - 1),
+                Some(_) => break,
+                None => {}
+            }
+            continue;
+        }
+
+        // any key press stops the countdown
+        if remaining.take().is_some() {
+            if let Some(timer) = &timer {
+                timer.cancel(st.boot_services()).fix(info!())?;
+            }
+        }
+
+        match st.stdin().read_key().fix(info!())? {
+            Some(Key::Special(ScanCode::UP)) => {
+                selected = selected.checked_sub(1).unwrap_or(items.len() - 1);
+            }
+            Some(Key::Special(ScanCode::DOWN)) => selected = (selected + 1) % items.len(),
+            Some(Key::Special(ScanCode::HOME)) => selected = 0,
+            Some(Key::Special(ScanCode::END)) => selected = items.len() - 1,
+            Some(Key::Printable(k)) if [0xD, 0xA].contains(&u16::from(k)) => break,
+            Some(Key::Special(ScanCode::ESCAPE)) => {
+                st.runtime_services()
+                    .reset(ResetType::Shutdown, Status::SUCCESS, None)
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(timer) = &timer {
+        timer.cancel(st.boot_services()).fix(info!())?;
+    }
+
+    st.stdout().clear().fix(info!())?;
+    Ok(selected)
+}
+
+fn draw(
+    st: &mut SystemTable<Boot>,
+    title: &str,
+    items: &[&str],
+    selected: usize,
+    remaining: Option<u64>,
+) -> Result {
+    let out = st.stdout();
+    out.clear().fix(info!())?;
+    writeln!(out, "{}\n", title).unwrap();
+
+    for (i, item) in items.iter().enumerate() {
+        if i == selected {
+            out.set_color(Color::Black, Color::LightGray).fix(info!())?;
+            writeln!(out, "> {}", item).unwrap();
+            out.set_color(Color::LightGray, Color::Black).fix(info!())?;
+        } else {
+            writeln!(out, "  {}", item).unwrap();
+        }
+    }
+
+    match remaining {
+        Some(secs) => write!(out, "\nbooting the selected entry in {}s..", secs).unwrap(),
+        None => write!(out, "\nuse the arrow keys to select, enter to boot").unwrap(),
+    }
+    Ok(())
+}
@@ -85,6 +85,19 @@ impl OpalCommand {
     }
 }
 
+fn is_byte_atom(token: &[u8]) -> bool {
+    match token[0] {
+        // short
+        0x80..=0xBF => token[0] & 0x20 != 0,
+        // medium
+        0xC0..=0xDF => token[0] & 0x10 != 0,
+        // long
+        0xE0..=0xE3 => token[0] & 0x02 != 0,
+        // tiny atoms and tokens
+        _ => false,
+    }
+}
+
 pub struct OpalResponse {
     pub header: OpalHeader,
     pub tokens: Vec<Vec<u8>>,
@@ -143,6 +156,103 @@ impl OpalResponse {
         self.tokens.get(index).map(Vec::as_slice) == Some(&[token.token])
     }
 
+    /// The index of the value of a column from the result of a Get, if it was returned
+    pub fn column_index(&self, column: SimpleToken) -> Option<usize> {
+        (0..self.len().saturating_sub(2))
+            .find(|&i| self.is(i, token::STARTNAME) && self.is(i + 1, column))
+            .map(|i| i + 2)
+    }
+
+    pub fn get_column(&self, column: SimpleToken) -> Option<u64> {
+        self.column_index(column).and_then(|i| self.try_uint(i))
+    }
+
+    pub fn get_column_bytes(&self, column: SimpleToken) -> Option<&[u8]> {
+        self.column_index(column).map(|i| self.get_bytes(i))
+    }
+
+    /// The byte atoms of a column from the result of a Get, either a single one or a list,
+    /// for the columns that are named with a number that's not a tiny atom
+    pub fn get_column_byte_list(&self, column: u64) -> Option<Vec<&[u8]>> {
+        let mut name = Vec::new();
+        column.write(&mut name);
+        let index = (0..self.len().saturating_sub(2))
+            .find(|&i| self.is(i, token::STARTNAME) && self.tokens[i + 1] == name)?
+            + 2;
+        if !self.is(index, token::STARTLIST) {
+            return is_byte_atom(&self.tokens[index]).then(|| vec![self.get_bytes(index)]);
+        }
+        Some(
+            (index + 1..self.len())
+                .take_while(|&i| !self.is(i, token::ENDLIST))
+                .filter(|&i| is_byte_atom(&self.tokens[i]))
+                .map(|i| self.get_bytes(i))
+                .collect(),
+        )
+    }
+
+    /// The byte atoms named with the given byte atom anywhere in the response,
+    /// e.g. the authorities of a BooleanExpr
+    pub fn get_named_bytes(&self, name: &[u8]) -> Vec<&[u8]> {
+        (0..self.len().saturating_sub(2))
+            .filter(|&i| {
+                self.is(i, token::STARTNAME)
+                    && is_byte_atom(&self.tokens[i + 1])
+                    && self.get_bytes(i + 1) == name
+                    && is_byte_atom(&self.tokens[i + 2])
+            })
+            .map(|i| self.get_bytes(i + 2))
+            .collect()
+    }
+
+    /// The value of a property from the result of a Properties call, the first
+    /// one with the name is taken, which is the TPer one and not the host one
+    pub fn get_property(&self, name: &[u8]) -> Option<u64> {
+        (0..self.len().saturating_sub(2))
+            .find(|&i| {
+                self.is(i, token::STARTNAME)
+                    && is_byte_atom(&self.tokens[i + 1])
+                    && self.get_bytes(i + 1) == name
+            })
+            .and_then(|i| self.try_uint(i + 2))
+    }
+
+    /// The first byte atom, e.g. the result of a Get from a byte table
+    pub fn first_bytes(&self) -> Option<&[u8]> {
+        let index = self.tokens.iter().position(|t| is_byte_atom(t))?;
+        Some(self.get_bytes(index))
+    }
+
+    /// The contents of a byte atom
+    pub fn get_bytes(&self, index: usize) -> &[u8] {
+        let token = &self.tokens[index];
+
+        if token[0] & 0x80 == 0 {
+            panic!("bytes requested for tiny atom")
+        } else if token[0] & 0x40 == 0 {
+            // short atom
+            &token[1..]
+        } else if token[0] & 0x20 == 0 {
+            // medium atom
+            &token[2..]
+        } else if token[0] & 0x10 == 0 {
+            // long atom
+            &token[4..]
+        } else {
+            panic!("bytes requested for token")
+        }
+    }
+
+    /// Same as `get_uint`, but None for anything that's not an unsigned integer
+    pub fn try_uint(&self, index: usize) -> Option<u64> {
+        let token = self.tokens.get(index)?;
+        match token[0] {
+            0x00..=0x3F => Some(token[0] as u64),
+            0x80..=0x8F if token.len() <= 9 => Some(self.get_uint(index)),
+            _ => None,
+        }
+    }
+
     pub fn get_uint(&self, index: usize) -> u64 {
         let token = &self.tokens[index];
 
@@ -156,16 +266,11 @@ impl OpalResponse {
         } else if token[0] & 0x40 == 0 {
             // short atom
             if token[0] & 0x10 == 0 {
-                let mut whatever = 0;
                 if token.len() > 9 {
                     panic!("u64 with more than 8 bytes");
                 }
-                let mut i = token.len();
-                for b in 0..token.len() {
-                    i -= 1;
-                    whatever |= (token[i] as u64) << (8 * b);
-                }
-                whatever
+                // big endian, after the header byte
+                token[1..].iter().fold(0, |n, &b| n << 8 | b as u64)
             } else {
                 panic!("unsigned int requested for signed short atom")
             }
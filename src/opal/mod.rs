@@ -167,6 +167,7 @@ pub mod uid {
         ENTERPRISE_BANDMASTER0 = 0x900008001;
         ENTERPRISE_ERASEMASTER = 0x900008401;
         // tables
+        OPAL_LOCKING_TABLE = 0x80200000000;
         OPAL_LOCKINGRANGE_GLOBAL = 0x80200000001;
         OPAL_LOCKINGRANGE_ACE_RDLOCKED = 0x80003E001;
         OPAL_LOCKINGRANGE_ACE_WRLOCKED = 0x80003E801;
@@ -175,6 +176,8 @@ pub mod uid {
         OPAL_MBRCONTROL_SET_DONE_TO_DOR = 0x80003F801;
         OPAL_MBRCONTROL = 0x80300000001;
         OPAL_MBR = 0x80400000000;
+        // the row of the MBR table in the Table table
+        OPAL_TABLE_MBR = 0x100000804;
         OPAL_AUTHORITY_TABLE = 0x900000000;
         OPAL_C_PIN_TABLE = 0xB00000000;
         OPAL_LOCKING_INFO_TABLE = 0x80100000001;
@@ -215,8 +218,16 @@ pub mod token {
         ENDCOLUMN = 0x04;
         VALUES = 0x01;
 
+        // table table
+        ROWS = 0x07;
+
         // authority table
+        ENABLED = 0x05;
+
+        // c_pin table
         PIN = 0x03;
+        TRYLIMIT = 0x05;
+        TRIES = 0x06;
 
         // locking tokens
         RANGESTART = 0x03;
@@ -225,11 +236,15 @@ pub mod token {
         WRITELOCKENABLED = 0x06;
         READLOCKED = 0x07;
         WRITELOCKED = 0x08;
+        LOCKONRESET = 0x09;
         ACTIVEKEY = 0x0A;
 
         //locking info table
         MAXRANGES = 0x04;
 
+        // ace table
+        BOOLEANEXPR = 0x03;
+
         // mbr control
         MBRENABLE = 0x01;
         MBRDONE = 0x02;
@@ -303,6 +318,59 @@ impl Token for LockingState {
     }
 }
 
+/// The Locking SP authorities that have a password
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Authority {
+    Admin(u8),
+    User(u8),
+}
+
+impl Authority {
+    /// Parses `admin1`, `user2` etc, the numbers start from 1
+    pub fn parse(authority: &str) -> Option<Self> {
+        let (kind, n): (fn(u8) -> Self, _) = if let Some(n) = authority.strip_prefix("admin") {
+            (Self::Admin, n)
+        } else {
+            (Self::User, authority.strip_prefix("user")?)
+        };
+        match n.parse() {
+            Ok(n) if n > 0 => Some(kind(n)),
+            _ => None,
+        }
+    }
+
+    fn row(self, table: u8) -> [u8; 8] {
+        match self {
+            Self::Admin(n) => [0x00, 0x00, 0x00, table, 0x00, 0x01, 0x00, n],
+            Self::User(n) => [0x00, 0x00, 0x00, table, 0x00, 0x03, 0x00, n],
+        }
+    }
+
+    pub fn uid(self) -> BS8 {
+        BS8::new(self.row(0x09), "AUTHORITY_N")
+    }
+
+    /// The row of the C_PIN table with the password of the authority
+    pub fn c_pin_uid(self) -> BS8 {
+        BS8::new(self.row(0x0B), "C_PIN_N")
+    }
+}
+
+impl core::fmt::Display for Authority {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Admin(n) => write!(f, "Admin{}", n),
+            Self::User(n) => write!(f, "User{}", n),
+        }
+    }
+}
+
+impl Default for Authority {
+    fn default() -> Self {
+        Self::Admin(1)
+    }
+}
+
 newtype_enum! {
     #[must_use]
     pub enum StatusCode: u8 => {
@@ -411,9 +479,15 @@ impl Token for &[u8] {
             // medium atom len
             buffer.push(0xD0 | ((self.len() >> 8) & 0x07) as u8);
             buffer.push((self.len() & 0xff) as u8);
+        } else if self.len() < 1 << 24 {
+            // long atom len
+            buffer.push(0xE2);
+            buffer.push((self.len() >> 16) as u8);
+            buffer.push((self.len() >> 8) as u8);
+            buffer.push(self.len() as u8);
         } else {
             panic!(
-                "Bytestring too large ({} > 2048) to use in OpalPacket",
+                "Bytestring too large ({} >= 2^24) to use in OpalPacket",
                 self.len()
             );
         }
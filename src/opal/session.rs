@@ -1,11 +1,12 @@
-use alloc::string::String;
-use core::{fmt::Write, mem::size_of_val, time::Duration};
+use alloc::{string::String, vec::Vec};
+use core::{convert::TryInto, fmt::Write, mem::size_of_val, time::Duration};
 
 use crate::{
     error::{Error, OpalError, Result},
     opal::{
         command::{OpalCommand, OpalCommandBuilder, OpalResponse},
-        method, tiny_atom, token, uid, LockingState, OpalHeader, SimpleToken, StatusCode, BS8,
+        method, tiny_atom, token, uid, Authority, LockingState, OpalHeader, SimpleToken,
+        StatusCode, Token, BS8,
     },
     secure_device::SecureDevice,
     token_list, token_name, tokens,
@@ -17,6 +18,116 @@ pub struct OpalSession<'d> {
     tsn: u32,
     hsn: u32,
     protocol: u8,
+    /// for the responses, the TPer doesn't send more than the negotiated ComPacket size
+    buffer_size: usize,
+}
+
+/// The packet size limits, the smaller ones of the TPer and of the host
+#[derive(Debug, Copy, Clone)]
+pub struct Properties {
+    pub max_com_packet_size: usize,
+    pub max_packet_size: usize,
+    pub max_ind_token_size: usize,
+}
+
+impl Default for Properties {
+    /// The minimums every TPer has to support
+    fn default() -> Self {
+        Self {
+            max_com_packet_size: 2048,
+            max_packet_size: 2028,
+            max_ind_token_size: 1992,
+        }
+    }
+}
+
+impl Properties {
+    pub fn chunk_size(&self) -> usize {
+        // the packet and subpacket headers and the tokens around the data
+        let payload = (self.max_com_packet_size.saturating_sub(20))
+            .min(self.max_packet_size)
+            .saturating_sub(24 + 12 + 64)
+            .min(self.max_ind_token_size.saturating_sub(4));
+        // rounded down to whole blocks when possible, just to be nice
+        if payload >= 512 {
+            payload & !511
+        } else {
+            payload.max(1)
+        }
+    }
+}
+
+/// The properties the host asks for, the TPer answers with what it supports
+const HOST_MAX_COM_PACKET_SIZE: u64 = 65536;
+const HOST_MAX_PACKET_SIZE: u64 = HOST_MAX_COM_PACKET_SIZE - 20;
+const HOST_MAX_IND_TOKEN_SIZE: u64 = HOST_MAX_PACKET_SIZE - 24 - 12 - 20;
+
+/// A row of the Locking table, the positions are in logical blocks
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LockingRange {
+    pub start: u64,
+    pub length: u64,
+    pub read_lock_enabled: bool,
+    pub write_lock_enabled: bool,
+    pub read_locked: bool,
+    pub write_locked: bool,
+}
+
+/// The names of the optional parameters of RevertSP and of Activate
+const KEEP_GLOBAL_RANGE_KEY: u64 = 0x060000;
+const SINGLE_USER_SELECTION_LIST: u64 = 0x060000;
+
+/// The LockingInfo column with the ranges in the Single User Mode
+const SINGLE_USER_MODE_RANGES: u64 = 0x060000;
+
+pub fn properties(device: &mut SecureDevice) -> Result<Properties> {
+    let mut s = OpalSession {
+        device,
+        tsn: 0,
+        hsn: 0,
+        protocol: 0x01,
+        buffer_size: 2048,
+    };
+    let command = OpalCommandBuilder::new(uid::OPAL_SMUID, method::PROPERTIES)
+        .payload(token_list![token_name!(
+            token::HOSTPROPERTIES,
+            token_list![
+                token_name!(b"MaxComPacketSize", HOST_MAX_COM_PACKET_SIZE),
+                token_name!(b"MaxResponseComPacketSize", HOST_MAX_COM_PACKET_SIZE),
+                token_name!(b"MaxPacketSize", HOST_MAX_PACKET_SIZE),
+                token_name!(b"MaxIndTokenSize", HOST_MAX_IND_TOKEN_SIZE),
+                token_name!(b"MaxPackets", 1),
+                token_name!(b"MaxSubpackets", 1),
+                token_name!(b"MaxMethods", 1),
+            ]
+        )])
+        .build();
+    let response = unsafe { s.send_raw_command(command) }?;
+
+    let defaults = Properties::default();
+    let property = |name: &[u8], host: u64, default: usize| {
+        let tper = response.get_property(name).unwrap_or(default as u64);
+        tper.min(host) as usize
+    };
+    let properties = Properties {
+        max_com_packet_size: property(
+            b"MaxComPacketSize",
+            HOST_MAX_COM_PACKET_SIZE,
+            defaults.max_com_packet_size,
+        ),
+        max_packet_size: property(
+            b"MaxPacketSize",
+            HOST_MAX_PACKET_SIZE,
+            defaults.max_packet_size,
+        ),
+        max_ind_token_size: property(
+            b"MaxIndTokenSize",
+            HOST_MAX_IND_TOKEN_SIZE,
+            defaults.max_ind_token_size,
+        ),
+    };
+    log::debug!("negotiated properties: {:?}", properties);
+    Ok(properties)
 }
 
 impl<'d> OpalSession<'d> {
@@ -31,33 +142,61 @@ impl<'d> OpalSession<'d> {
             tsn: 0,
             hsn: 0,
             protocol: 0x01,
+            buffer_size: 2048,
         };
 
-        let challenge_tokens = match challenge {
-            Some(challenge) if !s.device.is_eprise() => {
-                tokens![
-                    token_name!(tiny_atom::UINT_00, challenge),
-                    token_name!(tiny_atom::UINT_03, sign_authority),
-                ]
-            }
-            _ => tokens![],
+        let is_eprise = s.device.is_eprise();
+        let command = || {
+            let challenge_tokens = match challenge {
+                Some(challenge) if !is_eprise => {
+                    tokens![
+                        token_name!(tiny_atom::UINT_00, challenge),
+                        token_name!(tiny_atom::UINT_03, sign_authority),
+                    ]
+                }
+                _ => tokens![],
+            };
+            OpalCommandBuilder::new(uid::OPAL_SMUID, method::STARTSESSION)
+                .payload(token_list![
+                    105, // our HSN, same as in sedutil, although looks like it can be arbitrary
+                    sp_uid,
+                    tiny_atom::UINT_01,
+                    challenge_tokens,
+                    if is_eprise {
+                        token_name!(b"SessionTimeout", 60000)
+                    } else {
+                        tokens![]
+                    }
+                ])
+                .build()
         };
 
-        let command = OpalCommandBuilder::new(uid::OPAL_SMUID, method::STARTSESSION)
-            .payload(token_list![
-                105, // our HSN, same as in sedutil, although looks like it can be arbitrary
-                sp_uid,
-                tiny_atom::UINT_01,
-                challenge_tokens,
-                if s.device.is_eprise() {
-                    token_name!(b"SessionTimeout", 60000)
-                } else {
-                    tokens![]
+        let mut reset = false;
+        let response = loop {
+            match unsafe { s.send_raw_command(command()) } {
+                // e.g. a previous boot stage left its session open
+                Err(Error::Opal(OpalError::Status(status)))
+                    if !reset
+                        && (status == StatusCode::SP_BUSY
+                            || status == StatusCode::NO_SESSIONS_AVAILABLE) =>
+                {
+                    log::warn!(
+                        "could not start a session ({:?}), resetting the ComID",
+                        status
+                    );
+                    match s.device.stack_reset() {
+                        Ok(completion) => completion.log(),
+                        Err(e) => {
+                            // the drive being busy is what the caller needs to know about
+                            log::warn!("could not reset the ComID: {:?}", e.status());
+                            return Err(status.into());
+                        }
+                    }
+                    reset = true;
                 }
-            ])
-            .build();
-
-        let response = unsafe { s.send_raw_command(command) }?;
+                response => break response?,
+            }
+        };
 
         s.hsn = response.get_uint(4) as _;
         s.tsn = response.get_uint(5) as _;
@@ -116,6 +255,11 @@ impl<'d> OpalSession<'d> {
         self
     }
 
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
     pub unsafe fn send_raw_command(&mut self, mut command: OpalCommand) -> Result<OpalResponse> {
         command.set_session(self.device.com_id(), self.tsn, self.hsn);
 
@@ -150,7 +294,8 @@ impl<'d> OpalSession<'d> {
             .map_err(|e| e.status())?
             .log();
 
-        let mut buffer = crate::util::alloc_uninit_aligned(2048, self.device.proto().align());
+        let mut buffer =
+            crate::util::alloc_uninit_aligned(self.buffer_size, self.device.proto().align());
 
         let mut header: OpalHeader;
         loop {
@@ -205,7 +350,7 @@ impl<'d> OpalSession<'d> {
         &mut self,
         table: BS8,
         name: SimpleToken,
-        value: SimpleToken,
+        value: impl Token,
     ) -> Result {
         self.send_raw_command(
             OpalCommandBuilder::new(table, method::SET)
@@ -218,8 +363,345 @@ impl<'d> OpalSession<'d> {
         Ok(())
     }
 
+    /// Reads the `start..=end` columns of a table row
+    pub fn get(
+        &mut self,
+        table: BS8,
+        start: SimpleToken,
+        end: SimpleToken,
+    ) -> Result<OpalResponse> {
+        let command = OpalCommandBuilder::new(table, method::GET)
+            .payload(token_list![token_list![
+                token_name!(token::STARTCOLUMN, start),
+                token_name!(token::ENDCOLUMN, end),
+            ]])
+            .build();
+        unsafe { self.send_raw_command(command) }
+    }
+
+    /// How many password tries the C_PIN row allows before the authority gets
+    /// locked out, `None` if there is no limit or the columns are not readable
+    pub fn remaining_tries(&mut self, c_pin: BS8) -> Result<Option<u64>> {
+        let response = self.get(c_pin, token::TRYLIMIT, token::TRIES)?;
+        let limit = response.get_column(token::TRYLIMIT);
+        let tries = response.get_column(token::TRIES);
+        Ok(match (limit, tries) {
+            (Some(limit), Some(tries)) if limit != 0 => Some(limit.saturating_sub(tries)),
+            _ => None,
+        })
+    }
+
+    /// Sets the password of the authority in its C_PIN row
+    pub fn set_pin(&mut self, c_pin: BS8, pin: &[u8]) -> Result {
+        unsafe { self.set_locking_sp_value(c_pin, token::PIN, pin) }
+    }
+
+    /// Reads the password of a C_PIN row, only the MSID one is readable in practice
+    pub fn get_pin(&mut self, c_pin: BS8) -> Result<Vec<u8>> {
+        let response = self.get(c_pin, token::PIN, token::PIN)?;
+        match response.get_column_bytes(token::PIN) {
+            Some(pin) => Ok(pin.to_vec()),
+            None => Err(OpalError::UnexpectedResponse.into()),
+        }
+    }
+
+    /// Activates an SP in the Manufactured-Inactive state, e.g. the Locking SP
+    pub fn activate(&mut self, sp_uid: BS8) -> Result {
+        let command = OpalCommandBuilder::new(sp_uid, method::ACTIVATE)
+            .payload(token_list![])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    /// Reverts the SP to its factory state, the TPer ends the session by itself.
+    /// Reverting the Admin SP (as the SID or the PSID) reverts the whole drive
+    /// and cryptographically erases everything on it
+    pub fn revert(mut self, sp_uid: BS8) -> Result {
+        let command = OpalCommandBuilder::new(sp_uid, method::REVERT)
+            .payload(token_list![])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        // the session is already gone, so no closing it on drop
+        self.tsn = 0;
+        Ok(())
+    }
+
+    /// Reverts the SP of the session to its factory state, the TPer ends the session
+    /// by itself. For the Locking SP this erases all the ranges, except for the global
+    /// one if `keep_global_range_key` is set
+    pub fn revert_sp(mut self, keep_global_range_key: bool) -> Result {
+        let command = OpalCommandBuilder::new(uid::OPAL_THISSP, method::REVERTSP)
+            .payload(token_list![token_name!(
+                KEEP_GLOBAL_RANGE_KEY,
+                SimpleToken::from(keep_global_range_key)
+            )])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        self.tsn = 0;
+        Ok(())
+    }
+
+    pub fn enable_authority(&mut self, authority: Authority, enable: bool) -> Result {
+        unsafe {
+            self.set_locking_sp_value(authority.uid(), token::ENABLED, SimpleToken::from(enable))
+        }
+    }
+
+    pub fn ace_authorities(&mut self, ace: BS8) -> Result<Vec<BS8>> {
+        let response = self.get(ace, token::BOOLEANEXPR, token::BOOLEANEXPR)?;
+        let authority_ref = &uid::OPAL_HALF_UID_AUTHORITY_OBJ_REF.bytes[..4];
+        Ok(response
+            .get_named_bytes(authority_ref)
+            .into_iter()
+            .filter_map(|bytes| Some(BS8::new(bytes.try_into().ok()?, "authority")))
+            .collect())
+    }
+
+    /// Sets who is allowed to do what the ACE is for, any one of the authorities is enough
+    pub fn set_ace(&mut self, ace: BS8, authorities: &[BS8]) -> Result {
+        let half = |uid: BS8| uid.bytes[..4].to_vec();
+        let (authority_ref, boolean_ace) = (
+            half(uid::OPAL_HALF_UID_AUTHORITY_OBJ_REF),
+            half(uid::OPAL_HALF_UID_BOOLEAN_ACE),
+        );
+        // the expression is in postfix, all the authorities and then an OR between each two
+        let mut expr = tokens![];
+        for &authority in authorities {
+            expr = tokens![expr, token_name!(authority_ref.as_slice(), authority)];
+        }
+        for _ in 1..authorities.len() {
+            expr = tokens![
+                expr,
+                token_name!(boolean_ace.as_slice(), tiny_atom::UINT_01)
+            ];
+        }
+        unsafe { self.set_locking_sp_value(ace, token::BOOLEANEXPR, token_list![expr]) }
+    }
+
+    pub fn allow_ace(&mut self, ace: BS8, authority: Authority, allow: bool) -> Result {
+        let uid = authority.uid();
+        let mut authorities = self.ace_authorities(ace)?;
+        authorities.retain(|a| a.bytes != uid.bytes);
+        if allow {
+            authorities.push(uid);
+        }
+        self.set_ace(ace, &authorities)
+    }
+
+    pub fn max_ranges(&mut self) -> Result<u64> {
+        let response = self.get(
+            uid::OPAL_LOCKING_INFO_TABLE,
+            token::MAXRANGES,
+            token::MAXRANGES,
+        )?;
+        response
+            .get_column(token::MAXRANGES)
+            .ok_or_else(|| OpalError::UnexpectedResponse.into())
+    }
+
+    pub fn gen_key(&mut self, locking_range: u8) -> Result {
+        let response = self.get(
+            locking_range_uid(locking_range),
+            token::ACTIVEKEY,
+            token::ACTIVEKEY,
+        )?;
+        // the UID of the K_AES_128 or K_AES_256 row with the key
+        let key = match response.get_column_bytes(token::ACTIVEKEY) {
+            Some(&[a, b, c, d, e, f, g, h]) => BS8::new([a, b, c, d, e, f, g, h], "ACTIVEKEY"),
+            _ => return Err(OpalError::UnexpectedResponse.into()),
+        };
+        let command = OpalCommandBuilder::new(key, method::GENKEY)
+            .payload(token_list![])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    /// Erases the range in the Single User Mode, which also replaces its key
+    /// and resets its password, has to be done by the user that owns the range
+    pub fn erase(&mut self, locking_range: u8) -> Result {
+        let command = OpalCommandBuilder::new(locking_range_uid(locking_range), method::ERASE)
+            .payload(token_list![])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    /// Activates the SP with the given ranges in the Single User Mode,
+    /// or all of them (including the ones that would be created later) if empty
+    pub fn activate_single_user(&mut self, sp_uid: BS8, ranges: &[u8]) -> Result {
+        let selection = if ranges.is_empty() {
+            uid::OPAL_LOCKING_TABLE.to_token_stream()
+        } else {
+            let mut list = tokens![];
+            for &range in ranges {
+                list = tokens![list, locking_range_uid(range)];
+            }
+            token_list![list]
+        };
+        let command = OpalCommandBuilder::new(sp_uid, method::ACTIVATE)
+            .payload(token_list![token_name!(
+                SINGLE_USER_SELECTION_LIST,
+                selection
+            )])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    pub fn is_single_user(&mut self, locking_range: u8) -> Result<bool> {
+        let command = OpalCommandBuilder::new(uid::OPAL_LOCKING_INFO_TABLE, method::GET)
+            .payload(token_list![token_list![
+                token_name!(token::STARTCOLUMN, SINGLE_USER_MODE_RANGES),
+                token_name!(token::ENDCOLUMN, SINGLE_USER_MODE_RANGES),
+            ]])
+            .build();
+        let response = unsafe { self.send_raw_command(command) }?;
+        let ranges = response
+            .get_column_byte_list(SINGLE_USER_MODE_RANGES)
+            .ok_or(OpalError::UnexpectedResponse)?;
+        // the whole Locking table means all of the ranges
+        let range = locking_range_uid(locking_range);
+        Ok(ranges
+            .iter()
+            .any(|&uid| uid == uid::OPAL_LOCKING_TABLE.bytes || uid == range.bytes))
+    }
+
+    pub fn set_mbr_enable(&mut self, enable: bool) -> Result {
+        unsafe {
+            self.set_locking_sp_value(
+                uid::OPAL_MBRCONTROL,
+                token::MBRENABLE,
+                SimpleToken::from(enable),
+            )
+        }
+    }
+
+    /// Enables or disables read and write locking of a range,
+    /// without which locking it does nothing
+    pub fn enable_locking_range(&mut self, locking_range: u8, enable: bool) -> Result {
+        self.set_lock_enabled(locking_range, enable, enable)
+    }
+
+    pub fn set_lock_enabled(&mut self, locking_range: u8, read: bool, write: bool) -> Result {
+        let command = OpalCommandBuilder::new(locking_range_uid(locking_range), method::SET)
+            .payload(token_list![token_name!(
+                token::VALUES,
+                token_list![
+                    token_name!(token::READLOCKENABLED, SimpleToken::from(read)),
+                    token_name!(token::WRITELOCKENABLED, SimpleToken::from(write)),
+                ]
+            )])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    pub fn set_range(&mut self, locking_range: u8, start: u64, length: u64) -> Result {
+        let command = OpalCommandBuilder::new(locking_range_uid(locking_range), method::SET)
+            .payload(token_list![token_name!(
+                token::VALUES,
+                token_list![
+                    token_name!(token::RANGESTART, start),
+                    token_name!(token::RANGELENGTH, length),
+                ]
+            )])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    pub fn set_lock_on_reset(&mut self, locking_range: u8, power_cycle: bool) -> Result {
+        // the list of the reset types, 0 is the power cycle
+        let resets = if power_cycle {
+            token_list![tiny_atom::UINT_00]
+        } else {
+            token_list![]
+        };
+        unsafe {
+            self.set_locking_sp_value(locking_range_uid(locking_range), token::LOCKONRESET, resets)
+        }
+    }
+
+    pub fn locking_range(&mut self, locking_range: u8) -> Result<LockingRange> {
+        let response = self.get(
+            locking_range_uid(locking_range),
+            token::RANGESTART,
+            token::WRITELOCKED,
+        )?;
+        // a range that silently reads as 0..0 would be worse than an error
+        let uint = |column| {
+            response
+                .get_column(column)
+                .ok_or(OpalError::UnexpectedResponse)
+        };
+        let bool = |column| matches!(uint(column), Ok(value) if value != 0);
+        Ok(LockingRange {
+            start: uint(token::RANGESTART)?,
+            length: uint(token::RANGELENGTH)?,
+            read_lock_enabled: bool(token::READLOCKENABLED),
+            write_lock_enabled: bool(token::WRITELOCKENABLED),
+            read_locked: bool(token::READLOCKED),
+            write_locked: bool(token::WRITELOCKED),
+        })
+    }
+
+    pub fn write_mbr(&mut self, offset: u64, data: &[u8]) -> Result {
+        let command = OpalCommandBuilder::new(uid::OPAL_MBR, method::SET)
+            .payload(token_list![
+                token_name!(token::WHERE, offset),
+                token_name!(token::VALUES, data),
+            ])
+            .build();
+        unsafe { self.send_raw_command(command) }?;
+        Ok(())
+    }
+
+    pub fn read_mbr(&mut self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let command = OpalCommandBuilder::new(uid::OPAL_MBR, method::GET)
+            .payload(token_list![token_list![
+                token_name!(token::STARTROW, offset),
+                token_name!(token::ENDROW, offset + len as u64 - 1),
+            ]])
+            .build();
+        let response = unsafe { self.send_raw_command(command) }?;
+        match response.first_bytes() {
+            Some(data) => Ok(data.to_vec()),
+            None => Err(OpalError::UnexpectedResponse.into()),
+        }
+    }
+
+    pub fn mbr_size(&mut self) -> Result<u64> {
+        let response = self.get(uid::OPAL_TABLE_MBR, token::ROWS, token::ROWS)?;
+        response
+            .get_column(token::ROWS)
+            .ok_or_else(|| OpalError::UnexpectedResponse.into())
+    }
+
+    pub fn read_mbr_chunks(
+        &mut self,
+        len: u64,
+        chunk_size: usize,
+        mut f: impl FnMut(&[u8]),
+    ) -> Result {
+        let mut offset = 0;
+        while offset < len {
+            let chunk = (len - offset).min(chunk_size as u64) as usize;
+            f(&self.read_mbr(offset, chunk)?);
+            offset += chunk as u64;
+        }
+        Ok(())
+    }
+
     pub fn set_mbr_done(&mut self, done: bool) -> Result {
-        unsafe { self.set_locking_sp_value(uid::OPAL_MBRCONTROL, token::MBRDONE, done.into()) }
+        unsafe {
+            self.set_locking_sp_value(
+                uid::OPAL_MBRCONTROL,
+                token::MBRDONE,
+                SimpleToken::from(done),
+            )
+        }
     }
 
     pub fn set_locking_range(&mut self, locking_range: u8, locking_state: LockingState) -> Result {
@@ -241,16 +723,7 @@ impl<'d> OpalSession<'d> {
             }
         }
 
-        let locking_range = if locking_range != 0 {
-            let mut bytes = uid::OPAL_LOCKINGRANGE_GLOBAL.bytes;
-            bytes[5] = 0x03;
-            bytes[7] = locking_range;
-            BS8::new(bytes, "LOCKING_RANGE_N")
-        } else {
-            uid::OPAL_LOCKINGRANGE_GLOBAL
-        };
-
-        let command = OpalCommandBuilder::new(locking_range, method::SET)
+        let command = OpalCommandBuilder::new(locking_range_uid(locking_range), method::SET)
             .payload(token_list![token_name!(
                 token::VALUES,
                 token_list![
@@ -271,6 +744,10 @@ impl<'d> OpalSession<'d> {
 
 impl<'d> Drop for OpalSession<'d> {
     fn drop(&mut self) {
+        // not a real session, e.g. the one for properties
+        if self.tsn == 0 {
+            return;
+        }
         let command = OpalCommandBuilder::empty()
             .payload(tokens![token::ENDOFSESSION])
             .build_no_end_of_data();
@@ -282,6 +759,29 @@ impl<'d> Drop for OpalSession<'d> {
     }
 }
 
+pub fn locking_range_ace_uid(locking_range: u8, write: bool) -> BS8 {
+    let ace = if write {
+        uid::OPAL_LOCKINGRANGE_GLOBAL_ACE_WRLOCKED
+    } else {
+        uid::OPAL_LOCKINGRANGE_GLOBAL_ACE_RDLOCKED
+    };
+    let mut bytes = ace.bytes;
+    bytes[7] = locking_range;
+    BS8::new(bytes, "LOCKINGRANGE_N_ACE")
+}
+
+/// The Locking table row of a range, 0 is the global one
+pub fn locking_range_uid(locking_range: u8) -> BS8 {
+    if locking_range != 0 {
+        let mut bytes = uid::OPAL_LOCKINGRANGE_GLOBAL.bytes;
+        bytes[5] = 0x03;
+        bytes[7] = locking_range;
+        BS8::new(bytes, "LOCKING_RANGE_N")
+    } else {
+        uid::OPAL_LOCKINGRANGE_GLOBAL
+    }
+}
+
 fn dump(title: &str, buffer: impl AsRef<[u8]>) {
     let mut dump = String::new();
     for (i, b) in buffer.as_ref().iter().enumerate() {
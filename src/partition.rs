@@ -0,0 +1,228 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+use uefi::{
+    prelude::*,
+    proto::{
+        device_path::DevicePath,
+        media::{
+            file::{File, FileSystemInfo},
+            fs::SimpleFileSystem,
+            partition::{GptPartitionEntry, GptPartitionType, PartitionInfo},
+        },
+    },
+    Guid,
+};
+
+use crate::{
+    dp_to_text::device_path_to_text,
+    error::{Error, Result, ResultFixupExt},
+    info, menu,
+    secure_device::SecureDevice,
+};
+
+/// Which partition a boot entry is loaded from
+#[derive(Debug, Clone)]
+pub enum PartitionSelector {
+    /// GPT unique partition GUID
+    Guid(String),
+    /// GPT partition name
+    Label(String),
+    /// filesystem volume label
+    FsLabel(String),
+    /// serial number of the drive, the ESP of which is used
+    Serial(String),
+    /// full device path text, as the firmware shows it
+    Path(String),
+}
+
+impl PartitionSelector {
+    pub fn parse(selector: &str) -> Option<Self> {
+        let (kind, value) = selector.split_once(':')?;
+        let value = value.trim().into();
+        Some(match kind.trim() {
+            "guid" => Self::Guid(value),
+            "label" => Self::Label(value),
+            "fs-label" => Self::FsLabel(value),
+            "serial" => Self::Serial(value),
+            "path" => Self::Path(value),
+            _ => return None,
+        })
+    }
+}
+
+/// Extended boot loader partition from the Boot Loader Specification
+const XBOOTLDR: GptPartitionType = GptPartitionType(Guid::from_values(
+    0xbc13c2ff,
+    0x59e6,
+    0x4262,
+    0xa352,
+    [0xb2, 0x75, 0xfd, 0x6f, 0x71, 0x72],
+));
+
+struct Partition {
+    handle: Handle,
+    path: String,
+    gpt: Option<GptPartitionEntry>,
+}
+
+impl Partition {
+    fn is_esp(&self) -> bool {
+        matches!(self.gpt, Some(gpt) if { gpt.partition_type_guid } == GptPartitionType::EFI_SYSTEM_PARTITION)
+    }
+
+    fn is_xbootldr(&self) -> bool {
+        matches!(self.gpt, Some(gpt) if { gpt.partition_type_guid } == XBOOTLDR)
+    }
+
+    fn label(&self) -> Option<String> {
+        let name = { self.gpt?.partition_name };
+        let label = name
+            .iter()
+            .map(|&c| u16::from(c))
+            .take_while(|&c| c != 0)
+            .map(|c| char::from_u32(c as u32).unwrap_or('?'))
+            .collect::<String>();
+        (!label.is_empty()).then_some(label)
+    }
+
+    fn describe(&self) -> String {
+        match self.label() {
+            Some(label) => format!("{} ({})", label, self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+fn guid_to_string(guid: Guid) -> String {
+    // EFI_GUID memory layout, first three groups are little-endian
+    let b: [u8; 16] = unsafe { core::mem::transmute(guid) };
+    let mut res = String::with_capacity(36);
+    for (i, idx) in [3, 2, 1, 0, 5, 4, 7, 6, 8, 9, 10, 11, 12, 13, 14, 15]
+        .iter()
+        .enumerate()
+    {
+        if [4, 6, 8, 10].contains(&i) {
+            res.push('-');
+        }
+        write!(&mut res, "{:02x}", b[*idx]).unwrap();
+    }
+    res
+}
+
+fn fs_label(st: &SystemTable<Boot>, handle: Handle) -> uefi::Result<String> {
+    let sfs = st
+        .boot_services()
+        .handle_protocol::<SimpleFileSystem>(handle)?
+        .log();
+    let info = unsafe { &mut *sfs.get() }
+        .open_volume()?
+        .log()
+        .get_boxed_info::<FileSystemInfo>()?
+        .log();
+    Ok(info.volume_label().as_string().into())
+}
+
+fn list_partitions(st: &SystemTable<Boot>, exclude: Handle) -> Result<Vec<Partition>> {
+    let bt = st.boot_services();
+    let mut result = Vec::new();
+    for handle in bt.find_handles::<SimpleFileSystem>().fix(info!())? {
+        if handle == exclude {
+            continue;
+        }
+        // a file system that can't be described can't be selected either
+        let dp = match bt.handle_protocol::<DevicePath>(handle).fix(info!()) {
+            Ok(dp) => dp,
+            Err(_) => continue,
+        };
+        let path = match device_path_to_text(unsafe { &*dp.get() }, false, false).fix(info!()) {
+            Ok(path) => path,
+            Err(e) => {
+                log::warn!("skipping a file system: {:?}", e);
+                continue;
+            }
+        };
+        let gpt = bt
+            .handle_protocol::<PartitionInfo>(handle)
+            .fix(info!())
+            .ok()
+            .and_then(|pi| unsafe { &*pi.get() }.gpt_partition_entry().copied());
+        result.push(Partition { handle, path, gpt });
+    }
+    Ok(result)
+}
+
+/// Lists the handles and device path texts of ESP and XBOOTLDR partitions
+/// other than the one of the greeter
+pub fn boot_partitions(st: &SystemTable<Boot>, exclude: Handle) -> Result<Vec<(Handle, String)>> {
+    Ok(list_partitions(st, exclude)?
+        .into_iter()
+        .filter(|p| p.is_esp() || p.is_xbootldr())
+        .map(|p| (p.handle, p.path))
+        .collect())
+}
+
+/// Finds the partition to boot from, excluding the one the greeter itself was loaded from.
+///
+/// Without a selector, the EFI system partitions are considered.
+/// If there are multiple candidates, the user is asked to choose.
+pub fn find_boot_partition(
+    st: &mut SystemTable<Boot>,
+    own_device: Handle,
+    selector: Option<&PartitionSelector>,
+    devices: &mut [SecureDevice],
+) -> Result<Handle> {
+    let mut drives = Vec::with_capacity(devices.len());
+    for device in devices.iter_mut() {
+        let dp = st
+            .boot_services()
+            .handle_protocol::<DevicePath>(device.handle())
+            .fix(info!())?;
+        let path = device_path_to_text(unsafe { &*dp.get() }, false, false).fix(info!())?;
+        let serial = String::from_utf8_lossy(device.proto().serial_num())
+            .trim()
+            .to_string();
+        drives.push((path, serial));
+    }
+
+    let mut candidates = Vec::new();
+    for partition in list_partitions(st, own_device)? {
+        let matches = match selector {
+            None => partition.is_esp(),
+            Some(PartitionSelector::Guid(guid)) => {
+                matches!(partition.gpt, Some(gpt) if guid_to_string(gpt.unique_partition_guid).eq_ignore_ascii_case(guid))
+            }
+            Some(PartitionSelector::Label(label)) => partition.label().as_ref() == Some(label),
+            Some(PartitionSelector::FsLabel(label)) => {
+                fs_label(st, partition.handle).fix(info!()).ok().as_ref() == Some(label)
+            }
+            Some(PartitionSelector::Serial(serial)) => {
+                partition.is_esp()
+                    && drives
+                        .iter()
+                        .any(|(path, s)| s == serial && partition.path.starts_with(path.as_str()))
+            }
+            Some(PartitionSelector::Path(path)) => partition.path.eq_ignore_ascii_case(path),
+        };
+        if matches {
+            candidates.push(partition);
+        }
+    }
+
+    match candidates.len() {
+        0 => Err(Error::NoBootPartitions),
+        1 => Ok(candidates[0].handle),
+        _ => {
+            let names = candidates
+                .iter()
+                .map(Partition::describe)
+                .collect::<Vec<_>>();
+            let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+            let chosen = menu::choose(st, "select the boot partition:", &names, 0, None)?;
+            Ok(candidates[chosen].handle)
+        }
+    }
+}
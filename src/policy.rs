@@ -0,0 +1,145 @@
+use alloc::{string::String, vec::Vec};
+
+/// A rough estimate of how hard the password is to guess
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    Weak,
+    Fair,
+    Good,
+    Strong,
+}
+
+impl Strength {
+    pub fn parse(strength: &str) -> Option<Self> {
+        match strength {
+            "weak" => Some(Self::Weak),
+            "fair" => Some(Self::Fair),
+            "good" => Some(Self::Good),
+            "strong" => Some(Self::Strong),
+            _ => None,
+        }
+    }
+
+    /// Estimates the strength from the size of the alphabet the password
+    /// draws from and its length, repeated characters in a row don't count
+    pub fn of(password: &str) -> Self {
+        let Classes {
+            lower,
+            upper,
+            digit,
+            other,
+        } = classes(password);
+        let pool = [(lower, 26), (upper, 26), (digit, 10), (other, 33)]
+            .iter()
+            .filter(|(present, _)| *present)
+            .map(|(_, size)| size)
+            .sum::<usize>();
+        let mut length = 0;
+        let mut last = None;
+        for c in password.chars() {
+            if last != Some(c) {
+                length += 1;
+            }
+            last = Some(c);
+        }
+        // bits per character rounded down, good enough for a meter
+        let bits = length * (usize::BITS - 1 - pool.max(1).leading_zeros()) as usize;
+        match bits {
+            0..=27 => Self::Weak,
+            28..=35 => Self::Fair,
+            36..=59 => Self::Good,
+            _ => Self::Strong,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Weak => "WEAK",
+            Self::Fair => "FAIR",
+            Self::Good => "GOOD",
+            Self::Strong => "STRONG",
+        }
+    }
+}
+
+struct Classes {
+    lower: bool,
+    upper: bool,
+    digit: bool,
+    other: bool,
+}
+
+fn classes(password: &str) -> Classes {
+    let any = |f: fn(char) -> bool| password.chars().any(f);
+    Classes {
+        lower: any(|c| c.is_lowercase()),
+        upper: any(|c| c.is_uppercase()),
+        digit: any(|c| c.is_ascii_digit()),
+        other: any(|c| !c.is_alphanumeric()),
+    }
+}
+
+/// What a new password has to satisfy when it's changed from the greeter
+#[derive(Debug)]
+pub struct Policy {
+    pub min_length: usize,
+    /// how many of lowercase, uppercase, digits and symbols are required
+    pub min_classes: usize,
+    pub min_strength: Strength,
+    /// lowercase, matched ignoring the case
+    pub denylist: Vec<String>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            min_classes: 1,
+            min_strength: Strength::Weak,
+            denylist: Vec::new(),
+        }
+    }
+}
+
+impl Policy {
+    /// Adds the passwords from a file with one password per line
+    pub fn extend_denylist(&mut self, text: &str) {
+        self.denylist.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_lowercase),
+        );
+    }
+
+    /// Returns the reason the password is rejected, if it is
+    pub fn check(&self, password: &str) -> Option<String> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Some(format!(
+                "the password needs at least {} characters",
+                self.min_length
+            ));
+        }
+        let Classes {
+            lower,
+            upper,
+            digit,
+            other,
+        } = classes(password);
+        let count = [lower, upper, digit, other].iter().filter(|&&c| c).count();
+        if count < self.min_classes {
+            return Some(format!(
+                "the password needs {} of lowercase, uppercase, digits and symbols",
+                self.min_classes
+            ));
+        }
+        if self.denylist.contains(&password.to_lowercase()) {
+            return Some("the password is too common".into());
+        }
+        if Strength::of(password) < self.min_strength {
+            return Some("the password is too weak".into());
+        }
+        None
+    }
+}
@@ -1,7 +1,7 @@
-use crate::BootServicesExt;
+use crate::{util::sleep, BootServicesExt};
 use alloc::boxed::Box;
 use bitflags::bitflags;
-use core::mem::MaybeUninit;
+use core::{mem::MaybeUninit, time::Duration};
 use uefi::{
     newtype_enum,
     table::{Boot, SystemTable},
@@ -30,12 +30,13 @@ newtype_enum! {
     pub enum FeatureCodes: u16 => {
         // TPER       = 0x0001,
         LOCKING    = 0x0002,
-        // GEOMETRY   = 0x0003,
+        GEOMETRY   = 0x0003,
         ENTERPRISE = 0x0100,
         // DATASTORE  = 0x0202,
-        // SINGLEUSER = 0x0201,
+        SINGLEUSER = 0x0201,
         // OPAL_V1    = 0x0200,
         OPAL_V2    = 0x0203,
+        BLOCK_SID  = 0x0402,
     }
 }
 
@@ -50,9 +51,42 @@ bitflags! {
     }
 }
 
+/// The Level 0 geometry reporting feature
+#[derive(Debug, Copy, Clone)]
+pub struct Geometry {
+    pub align: bool,
+    pub logical_block_size: u32,
+    /// in logical blocks
+    pub alignment_granularity: u64,
+    pub lowest_aligned_lba: u64,
+}
+
+/// The Level 0 Single User Mode feature
+#[derive(Debug, Copy, Clone)]
+pub struct SingleUser {
+    pub max_ranges: u32,
+    pub any: bool,
+    pub all: bool,
+    /// whether only the range owners (and not the admins) can move and resize them
+    pub policy: bool,
+}
+
+/// The Level 0 Block SID Authentication feature
+#[derive(Debug, Copy, Clone)]
+pub struct BlockSid {
+    pub sid_is_msid: bool,
+    /// whether authenticating as the SID is blocked until the next power cycle
+    pub blocked: bool,
+    /// whether a hardware reset also unblocks it
+    pub hardware_reset: bool,
+}
+
 #[derive(Debug)]
 pub struct SecureDeviceInfo {
     pub locking: Option<LockingFlags>,
+    pub geometry: Option<Geometry>,
+    pub single_user: Option<SingleUser>,
+    pub block_sid: Option<BlockSid>,
     pub opal_v2: Option<ComIdInfo>,
     pub enterprise: Option<ComIdInfo>,
 }
@@ -98,6 +132,10 @@ impl SecureDevice {
         Ok(().into())
     }
 
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
     pub fn com_id(&self) -> u16 {
         self.com_id
     }
@@ -110,6 +148,67 @@ impl SecureDevice {
         &mut *self.device
     }
 
+    /// The Level 0 locking feature flags, if the drive reports them
+    pub fn recv_locking(&mut self) -> uefi::Result<Option<LockingFlags>> {
+        Ok(recv_info(self.proto())?.log().locking.into())
+    }
+
+    pub fn recv_geometry(&mut self) -> uefi::Result<Option<Geometry>> {
+        Ok(recv_info(self.proto())?.log().geometry.into())
+    }
+
+    pub fn recv_single_user(&mut self) -> uefi::Result<Option<SingleUser>> {
+        Ok(recv_info(self.proto())?.log().single_user.into())
+    }
+
+    pub fn recv_block_sid(&mut self) -> uefi::Result<Option<BlockSid>> {
+        Ok(recv_info(self.proto())?.log().block_sid.into())
+    }
+
+    /// Blocks authenticating as the SID until the next power cycle (or also a hardware
+    /// reset), so that nothing booted after us can take ownership of the drive
+    pub fn block_sid(&mut self, clear_on_hardware_reset: bool) -> uefi::Result {
+        let mut buffer = crate::util::alloc_zeroed_aligned(512, self.proto().align());
+        // the Clear Events field
+        buffer[0] = clear_on_hardware_reset as u8;
+        unsafe { self.proto().secure_send(0x02, 0x0005, buffer.as_mut()) }
+    }
+
+    /// Aborts all the sessions and transactions on our ComID, e.g. the ones
+    /// left behind by a previous boot stage that didn't close them
+    pub fn stack_reset(&mut self) -> uefi::Result {
+        let com_id = self.com_id;
+        let mut buffer = crate::util::alloc_zeroed_aligned(512, self.proto().align());
+        buffer[0..2].copy_from_slice(&com_id.to_be_bytes());
+        // the request code, 2 is STACK_RESET
+        buffer[4..8].copy_from_slice(&2u32.to_be_bytes());
+        unsafe { self.proto().secure_send(0x02, com_id, buffer.as_mut()) }?.log();
+
+        // the response is there once its length is not zero
+        for _ in 0..40 {
+            let mut response =
+                unsafe { crate::util::alloc_uninit_aligned(512, self.proto().align()) };
+            unsafe { self.proto().secure_recv(0x02, com_id, &mut response) }?.log();
+            let response = unsafe { response.assume_init() };
+            if response[10..12] != [0, 0] {
+                return if response[12..16] == [0, 0, 0, 0] {
+                    Ok(().into())
+                } else {
+                    Err(Status::DEVICE_ERROR.into())
+                };
+            }
+            sleep(Duration::from_millis(25));
+        }
+        Err(Status::TIMEOUT.into())
+    }
+
+    /// Resets the TPer as if it was power cycled, the drive has to have it enabled
+    /// with the TPerReset feature, all the ranges that lock on reset get locked
+    pub fn tper_reset(&mut self) -> uefi::Result {
+        let mut buffer = crate::util::alloc_zeroed_aligned(512, self.proto().align());
+        unsafe { self.proto().secure_send(0x02, 0x0004, buffer.as_mut()) }
+    }
+
     pub fn recv_locked(&mut self) -> uefi::Result<bool> {
         Ok(recv_info(self.proto())?
             .log()
@@ -125,6 +224,9 @@ impl SecureDevice {
 fn recv_info(proto: &mut dyn SecureProtocol) -> uefi::Result<SecureDeviceInfo> {
     let mut device_info = SecureDeviceInfo {
         locking: None,
+        geometry: None,
+        single_user: None,
+        block_sid: None,
         opal_v2: None,
         enterprise: None,
     };
@@ -159,6 +261,46 @@ fn recv_info(proto: &mut dyn SecureProtocol) -> uefi::Result<SecureDeviceInfo> {
                     None => break,
                 })
             }
+            FeatureCodes::GEOMETRY => {
+                let feature = match buffer.get(offset..offset + 32) {
+                    Some(feature) => feature,
+                    None => break,
+                };
+                let be = |range: core::ops::Range<usize>| {
+                    feature[range].iter().fold(0, |n, &b| n << 8 | b as u64)
+                };
+                device_info.geometry = Some(Geometry {
+                    align: feature[4] & 0x01 != 0,
+                    logical_block_size: be(12..16) as u32,
+                    alignment_granularity: be(16..24),
+                    lowest_aligned_lba: be(24..32),
+                })
+            }
+            FeatureCodes::SINGLEUSER => {
+                let feature = match buffer.get(offset..offset + 9) {
+                    Some(feature) => feature,
+                    None => break,
+                };
+                let mut max_ranges = [0; 4];
+                max_ranges.copy_from_slice(&feature[4..8]);
+                device_info.single_user = Some(SingleUser {
+                    max_ranges: u32::from_be_bytes(max_ranges),
+                    any: feature[8] & 0x01 != 0,
+                    all: feature[8] & 0x02 != 0,
+                    policy: feature[8] & 0x04 != 0,
+                })
+            }
+            FeatureCodes::BLOCK_SID => {
+                let feature = match buffer.get(offset..offset + 6) {
+                    Some(feature) => feature,
+                    None => break,
+                };
+                device_info.block_sid = Some(BlockSid {
+                    sid_is_msid: feature[4] & 0x01 == 0,
+                    blocked: feature[4] & 0x02 != 0,
+                    hardware_reset: feature[5] & 0x01 != 0,
+                })
+            }
             FeatureCodes::ENTERPRISE => {
                 device_info.enterprise = Some(get_com_id(&buffer, offset + 4));
             }
@@ -0,0 +1,118 @@
+use bitflags::bitflags;
+use core::ffi::c_void;
+use uefi::{
+    data_types::unsafe_guid,
+    prelude::*,
+    proto::{console::text::ScanCode, Protocol},
+    Event,
+};
+
+/// EFI_SIMPLE_TEXT_INPUT_EX_PROTOCOL, which uefi-rs does not have
+#[unsafe_guid("dd9e7534-7762-4698-8c14-f58517a625aa")]
+#[derive(Protocol)]
+#[repr(C)]
+pub struct SimpleTextInputEx {
+    reset: unsafe extern "efiapi" fn(this: &mut SimpleTextInputEx, extended: bool) -> Status,
+    read_key_stroke_ex:
+        unsafe extern "efiapi" fn(this: &mut SimpleTextInputEx, key_data: &mut KeyData) -> Status,
+    wait_for_key_ex: Event,
+    set_state: unsafe extern "efiapi" fn(this: &mut SimpleTextInputEx, state: &u8) -> Status,
+    // key notifications are not used
+    register_key_notify: *const c_void,
+    unregister_key_notify: *const c_void,
+}
+
+bitflags! {
+    pub struct ShiftState: u32 {
+        const VALID = 0x80000000;
+        const RIGHT_SHIFT = 0x00000001;
+        const LEFT_SHIFT = 0x00000002;
+        const RIGHT_CONTROL = 0x00000004;
+        const LEFT_CONTROL = 0x00000008;
+        const RIGHT_ALT = 0x00000010;
+        const LEFT_ALT = 0x00000020;
+        const RIGHT_LOGO = 0x00000040;
+        const LEFT_LOGO = 0x00000080;
+        const MENU = 0x00000100;
+        const SYS_REQ = 0x00000200;
+    }
+}
+
+bitflags! {
+    pub struct ToggleState: u8 {
+        const VALID = 0x80;
+        const KEY_STATE_EXPOSED = 0x40;
+        const SCROLL_LOCK = 0x01;
+        const NUM_LOCK = 0x02;
+        const CAPS_LOCK = 0x04;
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct KeyData {
+    pub scan_code: u16,
+    pub unicode_char: u16,
+    pub shift_state: u32,
+    pub toggle_state: u8,
+}
+
+impl KeyData {
+    pub fn scan_code(&self) -> ScanCode {
+        ScanCode(self.scan_code)
+    }
+
+    /// Shift states are only reported if the valid bit is set
+    pub fn shift_state(&self) -> ShiftState {
+        let state = ShiftState::from_bits_truncate(self.shift_state);
+        if state.contains(ShiftState::VALID) {
+            state
+        } else {
+            ShiftState::empty()
+        }
+    }
+
+    pub fn toggle_state(&self) -> ToggleState {
+        let state = ToggleState::from_bits_truncate(self.toggle_state);
+        if state.contains(ToggleState::VALID) {
+            state
+        } else {
+            ToggleState::empty()
+        }
+    }
+}
+
+impl SimpleTextInputEx {
+    pub fn reset(&mut self, extended: bool) -> uefi::Result {
+        unsafe { (self.reset)(self, extended) }.into()
+    }
+
+    /// Returns `None` if there is no key press waiting
+    pub fn read_key_stroke(&mut self) -> uefi::Result<Option<KeyData>> {
+        let mut key_data = KeyData::default();
+        match unsafe { (self.read_key_stroke_ex)(self, &mut key_data) } {
+            Status::NOT_READY => Ok(None.into()),
+            status => status.into_with_val(|| Some(key_data)),
+        }
+    }
+
+    pub fn wait_for_key_event(&self) -> &Event {
+        &self.wait_for_key_ex
+    }
+
+    pub fn set_state(&mut self, state: ToggleState) -> uefi::Result {
+        unsafe { (self.set_state)(self, &state.bits()) }.into()
+    }
+}
+
+/// The handle of the console input device, uefi-rs only exposes its protocol
+pub fn console_in_handle(st: &SystemTable<Boot>) -> Handle {
+    #[repr(C)]
+    struct RawSystemTable {
+        header: [u8; 24],
+        firmware_vendor: *const u16,
+        firmware_revision: u32,
+        console_in_handle: Handle,
+    }
+    unsafe { (*(st.as_ptr() as *const RawSystemTable)).console_in_handle }
+}
@@ -1,5 +1,13 @@
-use alloc::{alloc::alloc, boxed::Box};
+use alloc::{
+    alloc::{alloc, alloc_zeroed},
+    boxed::Box,
+};
 use core::{alloc::Layout, mem::MaybeUninit, time::Duration};
+use uefi::{
+    prelude::BootServices,
+    table::boot::{EventType, TimerTrigger, Tpl},
+    Event,
+};
 
 pub fn sleep(duration: Duration) {
     // untie the sleep function from the system table
@@ -14,3 +22,44 @@ pub unsafe fn alloc_uninit_aligned(len: usize, align: usize) -> Box<[MaybeUninit
     let ptr = alloc(Layout::from_size_align(len, align).unwrap()) as _;
     Box::from_raw(core::slice::from_raw_parts_mut(ptr, len))
 }
+
+pub fn alloc_zeroed_aligned(len: usize, align: usize) -> Box<[u8]> {
+    unsafe {
+        let ptr = alloc_zeroed(Layout::from_size_align(len, align).unwrap());
+        Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len))
+    }
+}
+
+/// A periodic UEFI timer event that can be waited on along with other events
+pub struct Timer(Event);
+
+impl Timer {
+    pub fn periodic(bt: &BootServices, period_ms: u64) -> uefi::Result<Self> {
+        let event = unsafe { bt.create_event(EventType::TIMER, Tpl::APPLICATION, None) }?.log();
+        // the trigger time is in 100ns units
+        bt.set_timer(
+            unsafe { event.unsafe_clone() },
+            TimerTrigger::Periodic(period_ms * 10_000),
+        )?
+        .log();
+        Ok(Self(event).into())
+    }
+
+    pub fn event(&self) -> &Event {
+        &self.0
+    }
+
+    pub fn cancel(&self, bt: &BootServices) -> uefi::Result {
+        bt.set_timer(unsafe { self.0.unsafe_clone() }, TimerTrigger::Cancel)
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        // otherwise the event keeps firing until the image exits
+        let bt = unsafe { uefi_services::system_table().as_ref() }.boot_services();
+        if let Err(e) = bt.close_event(unsafe { self.0.unsafe_clone() }) {
+            log::warn!("could not close a timer event: {:?}", e.status());
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use uefi::{
+    table::runtime::{RuntimeServices, VariableAttributes, VariableVendor},
+    unsafe_guid, CString16, Identify, Status,
+};
+
+/// Vendor namespace for the variables owned by the greeter itself
+#[unsafe_guid("3b1ac0c5-5a3c-4e4b-9b0a-6f2d8e1c47a9")]
+struct GreeterVariables;
+
+pub fn greeter_vendor() -> VariableVendor {
+    VariableVendor(GreeterVariables::GUID)
+}
+
+/// Reads a whole variable, returning `None` if it does not exist
+pub fn get(
+    rt: &RuntimeServices,
+    name: &str,
+    vendor: &VariableVendor,
+) -> uefi::Result<Option<Vec<u8>>> {
+    let name = CString16::try_from(name).or(Err(Status::INVALID_PARAMETER))?;
+    let size = match rt.get_variable_size(&name, vendor) {
+        Ok(size) => size.log(),
+        Err(e) if e.status() == Status::NOT_FOUND => return Ok(None.into()),
+        Err(e) => return Err(e),
+    };
+    let mut buf = vec![0; size];
+    let (read, _) = rt.get_variable(&name, vendor, &mut buf)?.log();
+    buf.truncate(read);
+    Ok(Some(buf).into())
+}
+
+/// Writes a non-volatile variable accessible at both boot and runtime
+pub fn set(rt: &RuntimeServices, name: &str, vendor: &VariableVendor, data: &[u8]) -> uefi::Result {
+    let name = CString16::try_from(name).or(Err(Status::INVALID_PARAMETER))?;
+    rt.set_variable(
+        &name,
+        vendor,
+        VariableAttributes::NON_VOLATILE
+            | VariableAttributes::BOOTSERVICE_ACCESS
+            | VariableAttributes::RUNTIME_ACCESS,
+        data,
+    )
+}